@@ -0,0 +1,335 @@
+use super::channel::{integrate, slice, Channel};
+use super::encoding::Encode;
+use super::scramble::Scrambling;
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+use nannou_egui::egui;
+
+/// Errors counted at one Eb/N0 together with what theory predicts there.
+pub struct BerPoint {
+    pub eb_n0: f32,
+    pub errors: usize,
+    pub bits: usize,
+    pub theory: Option<f64>,
+}
+
+impl BerPoint {
+    pub fn ber(&self) -> f64 {
+        self.errors as f64 / self.bits.max(1) as f64
+    }
+}
+
+pub struct BerCurve {
+    pub name: String,
+    pub points: Vec<BerPoint>,
+}
+
+/// A sweep of Eb/N0 in dB from `from` to `to` with `bits` random bits per point.
+pub struct Ber {
+    pub from: f32,
+    pub to: f32,
+    pub step: f32,
+    pub bits: usize,
+    pub seed: u64,
+    pub curves: Vec<BerCurve>,
+    pub show_plot: bool,
+    /// The sweep the GUI is working through.
+    pub running: Option<Sweep>,
+}
+
+impl Default for Ber {
+    fn default() -> Self {
+        Ber {
+            from: 0.0,
+            to: 10.0,
+            step: 1.0,
+            bits: 20_000,
+            seed: 1,
+            curves: Vec::new(),
+            show_plot: false,
+            running: None,
+        }
+    }
+}
+
+/// Random bits simulated in one go, small enough for the GUI to run a few of
+/// them every frame.
+const CHUNK: usize = 10_000;
+
+/// Sends `data` through the line code, the AWGN channel and a threshold decoder
+/// and counts the bits decoded wrongly.
+pub fn simulate(encoding: &dyn Encode, data: &str, eb_n0: f32, seed: u64) -> usize {
+    let channel = Channel {
+        enabled: true,
+        eb_n0,
+        seed,
+        samples: 4,
+    };
+    let levels = encoding.encode(data, Scrambling::None);
+    let bits_per_element = encoding.bits_per_symbol() as f32 / encoding.elements_per_symbol() as f32;
+    let waveform = channel.transmit(&channel.oversample(&levels), bits_per_element);
+    let received = slice(&integrate(&waveform, channel.samples), &encoding.levels());
+    let decoded = encoding.decode(&received, Scrambling::None);
+    decoded.chars().zip(data.chars()).filter(|(decoded, sent)| decoded != sent).count()
+}
+
+/// A sweep under way, which moves on by one chunk of bits at a time so the GUI
+/// can spread it over frames.
+pub struct Sweep {
+    pub name: String,
+    pub points: Vec<BerPoint>,
+    from: f32,
+    step: f32,
+    steps: usize,
+    bits: usize,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Sweep {
+    pub fn new(name: String, ber: &Ber) -> Self {
+        Sweep {
+            name,
+            points: Vec::new(),
+            from: ber.from,
+            step: ber.step,
+            steps: ((ber.to - ber.from) / ber.step).floor().max(0.0) as usize + 1,
+            bits: ber.bits,
+            seed: ber.seed,
+            rng: StdRng::seed_from_u64(ber.seed),
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.points.len() == self.steps && self.points.last().is_some_and(|point| point.bits == self.bits)
+    }
+
+    /// Fraction of the bits simulated so far.
+    pub fn progress(&self) -> f32 {
+        let bits: usize = self.points.iter().map(|point| point.bits).sum();
+        bits as f32 / (self.bits * self.steps) as f32
+    }
+
+    /// Simulates the next chunk of bits, starting the next point when the last
+    /// one has all of its bits. Every point has a seed of its own.
+    pub fn advance(&mut self, encoding: &dyn Encode) {
+        if self.done() {
+            return;
+        }
+        if self.points.last().is_none_or(|point| point.bits == self.bits) {
+            let i = self.points.len();
+            let eb_n0 = self.from + self.step * i as f32;
+            self.rng = StdRng::seed_from_u64(self.seed + i as u64);
+            self.points.push(BerPoint {
+                eb_n0,
+                errors: 0,
+                bits: 0,
+                theory: encoding.theoretical_ber(10f64.powf(eb_n0 as f64 / 10.0)),
+            });
+        }
+        let point = self.points.last_mut().unwrap();
+        let len = CHUNK.min(self.bits - point.bits);
+        let data: String = (0..len).map(|_| if self.rng.gen() { '1' } else { '0' }).collect();
+        point.errors += simulate(encoding, &data, point.eb_n0, self.rng.gen());
+        point.bits += len;
+    }
+}
+
+pub fn sweep(encoding: &dyn Encode, ber: &Ber) -> Vec<BerPoint> {
+    let mut sweep = Sweep::new(format!("{:?}", encoding), ber);
+    while !sweep.done() {
+        sweep.advance(encoding);
+    }
+    sweep.points
+}
+
+/// Runs the sweep for the line codes named in `args` and returns it as CSV. The
+/// options --from, --to, --step, --bits and --seed change the sweep.
+pub fn report(args: &[String]) -> Result<String, String> {
+    let mut ber = Ber::default();
+    let mut names = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value", option))
+                .map(|value| value.to_string())
+        };
+        let invalid = |option: &str| format!("Invalid value for {}", option);
+        match arg.as_str() {
+            "--from" => ber.from = value(arg)?.parse().map_err(|_| invalid(arg))?,
+            "--to" => ber.to = value(arg)?.parse().map_err(|_| invalid(arg))?,
+            "--step" => ber.step = value(arg)?.parse().map_err(|_| invalid(arg))?,
+            "--bits" => ber.bits = value(arg)?.parse().map_err(|_| invalid(arg))?,
+            "--seed" => ber.seed = value(arg)?.parse().map_err(|_| invalid(arg))?,
+            name => names.push(name.to_string()),
+        }
+    }
+    if ber.step <= 0.0 {
+        return Err("--step must be positive".to_string());
+    }
+    if ber.bits == 0 {
+        return Err("--bits must be positive".to_string());
+    }
+    if names.is_empty() {
+        names = ["NRZ-L", "Manchester", "AMI", "2B1Q"].map(String::from).to_vec();
+    }
+
+    let mut curves = Vec::new();
+    for name in &names {
+        let encoding = super::encoding(name).ok_or_else(|| {
+            format!("Unknown line code {}, expected one of {}", name, super::ENCODINGS.join(", "))
+        })?;
+        curves.push(sweep(encoding.as_ref(), &ber));
+    }
+
+    let mut csv = String::from("Eb/N0 (dB)");
+    for name in &names {
+        csv.push_str(&format!(",{},{} theory", name, name));
+    }
+    csv.push('\n');
+    for (i, point) in curves[0].iter().enumerate() {
+        csv.push_str(&format!("{}", point.eb_n0));
+        for curve in &curves {
+            let theory = curve[i].theory.map(|theory| format!("{:.4e}", theory)).unwrap_or_default();
+            csv.push_str(&format!(",{:.4e},{}", curve[i].ber(), theory));
+        }
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+/// Plots every curve on a logarithmic BER axis, the measured points as dots and
+/// the theory as a line in the same colour.
+pub fn draw_plot(ui: &mut egui::Ui, ber: &Ber) {
+    const COLORS: [egui::Color32; 6] = [
+        egui::Color32::LIGHT_BLUE,
+        egui::Color32::LIGHT_GREEN,
+        egui::Color32::GOLD,
+        egui::Color32::LIGHT_RED,
+        egui::Color32::WHITE,
+        egui::Color32::KHAKI,
+    ];
+    let values = ber.curves.iter().flat_map(|curve| {
+        curve
+            .points
+            .iter()
+            .flat_map(|point| [Some(point.ber()), point.theory])
+            .flatten()
+    });
+    let lowest = values.filter(|&ber| ber > 0.0).fold(1.0, f64::min);
+    let decades = (-lowest.log10().floor()).clamp(1.0, 9.0) as i32;
+    let (from, to) = (ber.from, ber.to.max(ber.from + ber.step));
+
+    let (response, painter) = ui.allocate_painter(egui::vec2(480.0, 320.0), egui::Sense::hover());
+    let plot = response.rect.shrink2(egui::vec2(30.0, 15.0));
+    let plot = egui::Rect::from_min_max(plot.min + egui::vec2(20.0, 0.0), plot.max);
+    let position = |eb_n0: f32, ber: f64| {
+        let x = plot.left() + plot.width() * (eb_n0 - from) / (to - from);
+        let y = plot.top() + plot.height() * (-ber.log10() as f32 / decades as f32);
+        egui::pos2(x, y)
+    };
+
+    let grid = (1.0, egui::Color32::from_gray(70));
+    for decade in 0..=decades {
+        let y = position(from, 10f64.powi(-decade)).y;
+        painter.line_segment([egui::pos2(plot.left(), y), egui::pos2(plot.right(), y)], grid);
+        let label = format!("1e-{}", decade);
+        painter.text(egui::pos2(plot.left() - 5.0, y), egui::Align2::RIGHT_CENTER, label, egui::TextStyle::Small, egui::Color32::GRAY);
+    }
+    let ticks = ((to - from) / ber.step).round() as usize;
+    for i in 0..=ticks {
+        let eb_n0 = from + ber.step * i as f32;
+        let x = position(eb_n0, 1.0).x;
+        painter.line_segment([egui::pos2(x, plot.top()), egui::pos2(x, plot.bottom())], grid);
+        let label = format!("{}", eb_n0);
+        painter.text(egui::pos2(x, plot.bottom() + 3.0), egui::Align2::CENTER_TOP, label, egui::TextStyle::Small, egui::Color32::GRAY);
+    }
+
+    let floor = 10f64.powi(-decades);
+    for (i, curve) in ber.curves.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let theory: Vec<egui::Pos2> = curve
+            .points
+            .iter()
+            .filter_map(|point| point.theory.filter(|&ber| ber >= floor).map(|ber| position(point.eb_n0, ber)))
+            .collect();
+        for line in theory.windows(2) {
+            painter.line_segment([line[0], line[1]], (1.5, color));
+        }
+        for point in curve.points.iter().filter(|point| point.ber() >= floor && point.errors > 0) {
+            painter.circle_filled(position(point.eb_n0, point.ber()), 3.5, color);
+        }
+        let legend = egui::pos2(plot.right() - 5.0, plot.top() + 5.0 + 14.0 * i as f32);
+        painter.text(legend, egui::Align2::RIGHT_TOP, &curve.name, egui::TextStyle::Small, color);
+    }
+    ui.label("Eb/N0 (dB) against bit error rate. Points with no errors are left out.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::encoding;
+    use super::*;
+
+    fn theory(name: &str, db: f64) -> f64 {
+        encoding(name).unwrap().theoretical_ber(10f64.powf(db / 10.0)).unwrap()
+    }
+
+    #[test]
+    fn theoretical_ber() {
+        // The textbook 1e-5 of antipodal signalling at 9.6 dB, 3 dB later for
+        // Manchester whose half-bit pulses only use half of the energy.
+        assert!((theory("NRZ-L", 9.6) / 9.736e-6 - 1.0).abs() < 1e-3);
+        assert!((theory("Manchester", 12.6) / 9.96e-6 - 1.0).abs() < 1e-3);
+        assert!((theory("NRZ-I", 9.6) / theory("NRZ-L", 9.6) - 2.0).abs() < 1e-3);
+        assert!(theory("AMI", 9.6) > theory("NRZ-L", 9.6));
+        assert!(theory("2B1Q", 9.6) > theory("NRZ-L", 9.6));
+        assert!(encoding("Miller").unwrap().theoretical_ber(1.0).is_none());
+    }
+
+    #[test]
+    fn simulation_follows_theory() {
+        let ber = Ber {
+            from: 4.0,
+            to: 4.0,
+            bits: 100_000,
+            ..Ber::default()
+        };
+        let nrzl = encoding("NRZ-L").unwrap();
+        let points = sweep(nrzl.as_ref(), &ber);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].bits, 100_000);
+        assert!((points[0].ber() / theory("NRZ-L", 4.0) - 1.0).abs() < 0.15, "{}", points[0].ber());
+    }
+
+    #[test]
+    fn report_rejects_bad_options() {
+        let args = |args: &str| args.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(report(&args("--bits 0")), Err("--bits must be positive".to_string()));
+        assert_eq!(report(&args("--step 0")), Err("--step must be positive".to_string()));
+        assert!(report(&args("--bits")).is_err());
+        assert!(report(&args("--from 0 --to 0 --bits 100 NRZ-L")).unwrap().starts_with("Eb/N0 (dB),NRZ-L,NRZ-L theory\n0,"));
+    }
+
+    #[test]
+    fn sweep_advances_in_chunks() {
+        let ber = Ber {
+            from: 0.0,
+            to: 2.0,
+            bits: 25_000,
+            ..Ber::default()
+        };
+        let nrzl = encoding("NRZ-L").unwrap();
+        let mut running = Sweep::new("NRZ-L".to_string(), &ber);
+        let mut steps = 0;
+        while !running.done() {
+            running.advance(nrzl.as_ref());
+            steps += 1;
+        }
+        assert_eq!(steps, 3 * 3);
+        assert_eq!(running.progress(), 1.0);
+        let points = sweep(nrzl.as_ref(), &ber);
+        let errors = |points: &[BerPoint]| points.iter().map(|point| point.errors).collect::<Vec<_>>();
+        assert_eq!(errors(&running.points), errors(&points));
+    }
+}
@@ -0,0 +1,642 @@
+use super::scramble::Lfsr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockCoding {
+    FourBFiveB,
+    EightBTenB,
+    SixtyFourBSixtySixB,
+    None,
+}
+
+/// One code group on the line together with the symbol it carries.
+pub struct Block {
+    pub bits: String,
+    pub label: String,
+    pub control: bool,
+    /// Running disparity after the block, for the DC-balanced codes.
+    pub disparity: Option<i8>,
+    /// Number of leading bits that form a sync header rather than payload.
+    pub header: usize,
+}
+
+impl BlockCoding {
+    /// Number of input bits consumed by one data block.
+    pub fn data_bits(&self) -> usize {
+        match self {
+            BlockCoding::FourBFiveB => 4,
+            BlockCoding::EightBTenB => 8,
+            BlockCoding::SixtyFourBSixtySixB => 8,
+            BlockCoding::None => 1,
+        }
+    }
+
+    /// Number of zero bits appended to `len` input bits to fill the last block.
+    pub fn padding(&self, len: usize) -> usize {
+        (self.data_bits() - len % self.data_bits()) % self.data_bits()
+    }
+}
+
+pub fn block_code(data: &str, scheme: BlockCoding, control_symbols: bool) -> Vec<Block> {
+    let mut data = data.to_string();
+    data.push_str(&"0".repeat(scheme.padding(data.len())));
+    match scheme {
+        BlockCoding::FourBFiveB => four_b_five_b(&data, control_symbols),
+        BlockCoding::EightBTenB => eight_b_ten_b(&data, control_symbols),
+        BlockCoding::SixtyFourBSixtySixB => sixty_four_b_sixty_six_b(&data, control_symbols),
+        BlockCoding::None => Vec::new(),
+    }
+}
+
+/// Splits coded bits back into data bits, dropping control symbols. Every code
+/// group that cannot be decoded is reported as an error.
+pub fn block_decode(bits: &str, scheme: BlockCoding) -> (String, Vec<String>) {
+    match scheme {
+        BlockCoding::FourBFiveB => four_b_five_b_decode(bits),
+        BlockCoding::EightBTenB => eight_b_ten_b_decode(bits),
+        BlockCoding::SixtyFourBSixtySixB => sixty_four_b_sixty_six_b_decode(bits),
+        BlockCoding::None => (bits.to_string(), Vec::new()),
+    }
+}
+
+const FOUR_B_FIVE_B: [&str; 16] = [
+    "11110", "01001", "10100", "10101", "01010", "01011", "01110", "01111", "10010", "10011",
+    "10110", "10111", "11010", "11011", "11100", "11101",
+];
+
+const FOUR_B_FIVE_B_CONTROL: [(&str, &str); 5] = [
+    ("11111", "I"),
+    ("11000", "J"),
+    ("10001", "K"),
+    ("01101", "T"),
+    ("00111", "R"),
+];
+
+fn control_block(bits: &str, label: &str) -> Block {
+    Block {
+        bits: bits.to_string(),
+        label: label.to_string(),
+        control: true,
+        disparity: None,
+        header: 0,
+    }
+}
+
+/// Codes every nibble with the 4B/5B table. With `control_symbols` set the data is
+/// framed as a 100BASE-X stream: idle, J/K start delimiter, data, T/R end delimiter, idle.
+pub fn four_b_five_b(data: &str, control_symbols: bool) -> Vec<Block> {
+    let mut blocks = Vec::with_capacity(data.len() / 4 + 6);
+    if control_symbols {
+        blocks.push(control_block("11111", "I"));
+        blocks.push(control_block("11000", "J"));
+        blocks.push(control_block("10001", "K"));
+    }
+
+    for nibble in data.as_bytes().chunks(4) {
+        let value = nibble.iter().fold(0, |acc, &b| (acc << 1) | (b - b'0') as usize);
+        blocks.push(Block {
+            bits: FOUR_B_FIVE_B[value].to_string(),
+            label: format!("{:X}", value),
+            control: false,
+            disparity: None,
+            header: 0,
+        });
+    }
+
+    if control_symbols {
+        blocks.push(control_block("01101", "T"));
+        blocks.push(control_block("00111", "R"));
+        blocks.push(control_block("11111", "I"));
+    }
+    blocks
+}
+
+pub fn four_b_five_b_decode(bits: &str) -> (String, Vec<String>) {
+    let mut data = String::with_capacity(bits.len() * 4 / 5);
+    let mut errors = Vec::new();
+    for (i, group) in bits.as_bytes().chunks(5).enumerate() {
+        let group = std::str::from_utf8(group).unwrap();
+        if let Some(value) = FOUR_B_FIVE_B.iter().position(|&code| code == group) {
+            data.push_str(&format!("{:04b}", value));
+        } else if !FOUR_B_FIVE_B_CONTROL.iter().any(|&(code, _)| code == group) {
+            errors.push(format!("Group {}: invalid code group {}", i, group));
+        }
+    }
+    (data, errors)
+}
+
+/// 5B/6B sub-block codes (abcdei) for D0 - D31 as (RD-, RD+).
+const FIVE_B_SIX_B: [(&str, &str); 32] = [
+    ("100111", "011000"),
+    ("011101", "100010"),
+    ("101101", "010010"),
+    ("110001", "110001"),
+    ("110101", "001010"),
+    ("101001", "101001"),
+    ("011001", "011001"),
+    ("111000", "000111"),
+    ("111001", "000110"),
+    ("100101", "100101"),
+    ("010101", "010101"),
+    ("110100", "110100"),
+    ("001101", "001101"),
+    ("101100", "101100"),
+    ("011100", "011100"),
+    ("010111", "101000"),
+    ("011011", "100100"),
+    ("100011", "100011"),
+    ("010011", "010011"),
+    ("110010", "110010"),
+    ("001011", "001011"),
+    ("101010", "101010"),
+    ("011010", "011010"),
+    ("111010", "000101"),
+    ("110011", "001100"),
+    ("100110", "100110"),
+    ("010110", "010110"),
+    ("110110", "001001"),
+    ("001110", "001110"),
+    ("101110", "010001"),
+    ("011110", "100001"),
+    ("101011", "010100"),
+];
+
+/// 5B/6B sub-block code for K28 as (RD-, RD+).
+const K28_SIX_B: (&str, &str) = ("001111", "110000");
+
+/// 3B/4B sub-block codes (fghj) for Dx.0 - Dx.7 as (RD-, RD+). Dx.7 is the primary P7.
+const THREE_B_FOUR_B: [(&str, &str); 8] = [
+    ("1011", "0100"),
+    ("1001", "1001"),
+    ("0101", "0101"),
+    ("1100", "0011"),
+    ("1101", "0010"),
+    ("1010", "1010"),
+    ("0110", "0110"),
+    ("1110", "0001"),
+];
+
+/// Alternate A7 code, also used by every Kx.7 control character.
+const ALTERNATE_SEVEN: (&str, &str) = ("0111", "1000");
+
+/// 3B/4B sub-block codes for K28.0 - K28.7 as (RD-, RD+).
+const K28_FOUR_B: [(&str, &str); 8] = [
+    ("1011", "0100"),
+    ("0110", "1001"),
+    ("1010", "0101"),
+    ("1100", "0011"),
+    ("1101", "0010"),
+    ("0101", "1010"),
+    ("1001", "0110"),
+    ("0111", "1000"),
+];
+
+/// The x of the only Kx.7 characters besides K28.7.
+const K_SEVEN: [usize; 4] = [23, 27, 29, 30];
+
+fn pick<'a>(codes: (&'a str, &'a str), disparity: i8) -> &'a str {
+    if disparity < 0 {
+        codes.0
+    } else {
+        codes.1
+    }
+}
+
+/// Running disparity after sending `code` while at `disparity`.
+fn next_disparity(code: &str, disparity: i8) -> i8 {
+    let ones = code.chars().filter(|&c| c == '1').count() * 2;
+    match ones.cmp(&code.len()) {
+        std::cmp::Ordering::Greater => 1,
+        std::cmp::Ordering::Less => -1,
+        std::cmp::Ordering::Equal => match code {
+            "000111" | "0011" => 1,
+            "111000" | "1100" => -1,
+            _ => disparity,
+        },
+    }
+}
+
+/// Whether Dx.7 takes A7 instead of P7 at `disparity`, the running disparity
+/// after its 6b sub-block, so that no run of five equal bits can appear.
+fn alternate_seven(x: usize, disparity: i8) -> bool {
+    match disparity {
+        -1 => [17, 18, 20].contains(&x),
+        _ => [11, 13, 14].contains(&x),
+    }
+}
+
+fn eight_b_ten_b_symbol(x: usize, y: usize, control: bool, disparity: &mut i8) -> Block {
+    let six = if control && x == 28 {
+        pick(K28_SIX_B, *disparity)
+    } else {
+        pick(FIVE_B_SIX_B[x], *disparity)
+    };
+    *disparity = next_disparity(six, *disparity);
+
+    let four = if control && x == 28 {
+        pick(K28_FOUR_B[y], *disparity)
+    } else if (control || alternate_seven(x, *disparity)) && y == 7 {
+        pick(ALTERNATE_SEVEN, *disparity)
+    } else {
+        pick(THREE_B_FOUR_B[y], *disparity)
+    };
+    *disparity = next_disparity(four, *disparity);
+
+    Block {
+        bits: format!("{}{}", six, four),
+        label: if control {
+            format!("K{}.{}", x, y)
+        } else {
+            format!("D{}.{}", x, y)
+        },
+        control,
+        disparity: Some(*disparity),
+        header: 0,
+    }
+}
+
+/// Codes every byte (most significant bit first) with 8B/10B, starting at RD-.
+/// With `control_symbols` set the data is framed by a K28.5 comma, the K27.7 start
+/// and the K29.7/K23.7 terminate characters.
+pub fn eight_b_ten_b(data: &str, control_symbols: bool) -> Vec<Block> {
+    let mut disparity = -1;
+    let mut blocks = Vec::with_capacity(data.len() / 8 + 5);
+    if control_symbols {
+        for (x, y) in [(28, 5), (27, 7)] {
+            blocks.push(eight_b_ten_b_symbol(x, y, true, &mut disparity));
+        }
+    }
+
+    for byte in data.as_bytes().chunks(8) {
+        let value = byte.iter().fold(0, |acc, &b| (acc << 1) | (b - b'0') as usize);
+        blocks.push(eight_b_ten_b_symbol(value & 0x1f, value >> 5, false, &mut disparity));
+    }
+
+    if control_symbols {
+        for (x, y) in [(29, 7), (23, 7), (28, 5)] {
+            blocks.push(eight_b_ten_b_symbol(x, y, true, &mut disparity));
+        }
+    }
+    blocks
+}
+
+/// Looks `code` up in `table` and reports whether it was found in the column for
+/// `disparity` or only in the opposite one.
+fn lookup(table: &[(&str, &str)], code: &str, disparity: i8) -> Option<(usize, bool)> {
+    let column = |i: usize| pick(table[i], disparity);
+    let other = |i: usize| pick(table[i], -disparity);
+    (0..table.len())
+        .find(|&i| column(i) == code)
+        .map(|i| (i, true))
+        .or_else(|| (0..table.len()).find(|&i| other(i) == code).map(|i| (i, false)))
+}
+
+pub fn eight_b_ten_b_decode(bits: &str) -> (String, Vec<String>) {
+    let mut data = String::with_capacity(bits.len() * 4 / 5);
+    let mut errors = Vec::new();
+    let mut disparity = -1;
+
+    for (i, group) in bits.as_bytes().chunks(10).enumerate() {
+        let group = std::str::from_utf8(group).unwrap();
+        if group.len() < 10 {
+            errors.push(format!("Symbol {}: truncated code group {}", i, group));
+            break;
+        }
+        let (six, four) = group.split_at(6);
+
+        let k28 = lookup(&[K28_SIX_B], six, disparity);
+        let x = match k28.or_else(|| lookup(&FIVE_B_SIX_B, six, disparity)) {
+            Some((x, in_column)) => {
+                if !in_column {
+                    errors.push(format!("Symbol {}: disparity error in 6b sub-block {}", i, six));
+                }
+                if k28.is_some() {
+                    28
+                } else {
+                    x
+                }
+            }
+            None => {
+                errors.push(format!("Symbol {}: invalid 6b sub-block {}", i, six));
+                disparity = next_disparity(six, disparity);
+                continue;
+            }
+        };
+        disparity = next_disparity(six, disparity);
+
+        let seven = lookup(&[ALTERNATE_SEVEN], four, disparity).map(|(_, in_column)| (7, in_column));
+        let (y, control) = match k28 {
+            Some(_) => (lookup(&K28_FOUR_B, four, disparity), true),
+            None => match seven {
+                Some(_) => (seven, K_SEVEN.contains(&x)),
+                None => (lookup(&THREE_B_FOUR_B, four, disparity), false),
+            },
+        };
+        match y {
+            Some((y, in_column)) => {
+                if !in_column {
+                    errors.push(format!("Symbol {}: disparity error in 4b sub-block {}", i, four));
+                }
+                if !control && y == 7 && seven.is_some() != alternate_seven(x, disparity) {
+                    let expected = if seven.is_some() { "P7" } else { "A7" };
+                    errors.push(format!("Symbol {}: D{}.7 has to use {} here", i, x, expected));
+                }
+                if !control {
+                    data.push_str(&format!("{:08b}", (y << 5) | x));
+                }
+            }
+            None => errors.push(format!("Symbol {}: invalid 4b sub-block {}", i, four)),
+        }
+        disparity = next_disparity(four, disparity);
+    }
+    (data, errors)
+}
+
+const DATA_HEADER: &str = "01";
+const CONTROL_HEADER: &str = "10";
+
+/// Block type of the idle control block, all eight control codes are /I/ (0x00).
+const IDLE: u8 = 0x1e;
+/// Block type of the start block, /S/ followed by seven data bytes.
+const START: u8 = 0x78;
+/// Block types of the terminate blocks, indexed by the number of data bytes before /T/.
+const TERMINATE: [u8; 8] = [0x87, 0x99, 0xaa, 0xb4, 0xcc, 0xd2, 0xe1, 0xff];
+
+/// `bits` least significant bits of `value`, least significant first as they go on the line.
+fn lsb_first(value: u8, bits: usize) -> String {
+    (0..bits).map(|i| if (value >> i) & 1 == 1 { '1' } else { '0' }).collect()
+}
+
+fn from_lsb_first(bits: &str) -> u8 {
+    bits.chars().rev().fold(0, |acc, c| (acc << 1) | (c == '1') as u8)
+}
+
+fn sixty_four_b_block(header: &str, payload: String, label: String, state: &mut u64) -> Block {
+    Block {
+        bits: format!("{}{}", header, Lfsr::pcs().scramble(&payload, state)),
+        label,
+        control: header == CONTROL_HEADER,
+        disparity: None,
+        header: 2,
+    }
+}
+
+/// Frames the bytes (most significant bit first) into 66-bit blocks: a 2-bit sync
+/// header followed by a 64-bit payload that runs through the x^58 + x^39 + 1
+/// scrambler. Without `control_symbols` the bytes fill data blocks and the last one
+/// is zero filled. With it they form a packet between idle blocks, opened by a start
+/// block and closed by the terminate block that fits the bytes left over; a packet
+/// shorter than the seven bytes of the start block is zero filled.
+pub fn sixty_four_b_sixty_six_b(data: &str, control_symbols: bool) -> Vec<Block> {
+    let bytes: Vec<u8> = data
+        .as_bytes()
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &b| (acc << 1) | (b - b'0')))
+        .collect();
+    let mut state = Lfsr::pcs().seed;
+    let mut blocks = Vec::with_capacity(bytes.len() / 8 + 4);
+
+    if !control_symbols {
+        for chunk in bytes.chunks(8) {
+            let mut payload: String = chunk.iter().map(|&byte| lsb_first(byte, 8)).collect();
+            payload.push_str(&"0".repeat(64 - payload.len()));
+            blocks.push(sixty_four_b_block(DATA_HEADER, payload, "Data".to_string(), &mut state));
+        }
+        return blocks;
+    }
+
+    let control = |block_type: u8, data: &[u8], state: &mut u64| {
+        let mut payload = lsb_first(block_type, 8);
+        payload.extend(data.iter().map(|&byte| lsb_first(byte, 8)));
+        payload.push_str(&"0".repeat(64 - payload.len()));
+        let label = format!("Type 0x{:02X}", block_type);
+        sixty_four_b_block(CONTROL_HEADER, payload, label, state)
+    };
+
+    blocks.push(control(IDLE, &[], &mut state));
+    let (start, rest) = bytes.split_at(bytes.len().min(7));
+    blocks.push(control(START, start, &mut state));
+    let full = rest.len() / 8 * 8;
+    for chunk in rest[..full].chunks(8) {
+        let payload = chunk.iter().map(|&byte| lsb_first(byte, 8)).collect();
+        blocks.push(sixty_four_b_block(DATA_HEADER, payload, "Data".to_string(), &mut state));
+    }
+    let last = &rest[full..];
+    blocks.push(control(TERMINATE[last.len()], last, &mut state));
+    blocks.push(control(IDLE, &[], &mut state));
+    blocks
+}
+
+pub fn sixty_four_b_sixty_six_b_decode(bits: &str) -> (String, Vec<String>) {
+    let mut data = String::with_capacity(bits.len());
+    let mut errors = Vec::new();
+    let mut state = Lfsr::pcs().seed;
+    let push_bytes = |data: &mut String, payload: &str| {
+        for byte in payload.as_bytes().chunks(8) {
+            let byte = from_lsb_first(std::str::from_utf8(byte).unwrap());
+            data.push_str(&format!("{:08b}", byte));
+        }
+    };
+
+    for (i, block) in bits.as_bytes().chunks(66).enumerate() {
+        let block = std::str::from_utf8(block).unwrap();
+        if block.len() < 66 {
+            errors.push(format!("Block {}: truncated block of {} bits", i, block.len()));
+            break;
+        }
+        let (header, payload) = block.split_at(2);
+        let payload = Lfsr::pcs().descramble(payload, &mut state);
+        match header {
+            DATA_HEADER => push_bytes(&mut data, &payload),
+            CONTROL_HEADER => {
+                let block_type = from_lsb_first(&payload[..8]);
+                if block_type == START {
+                    push_bytes(&mut data, &payload[8..]);
+                } else if let Some(count) = TERMINATE.iter().position(|&t| t == block_type) {
+                    push_bytes(&mut data, &payload[8..8 + count * 8]);
+                } else if block_type != IDLE {
+                    errors.push(format!("Block {}: unknown block type 0x{:02X}", i, block_type));
+                }
+            }
+            _ => errors.push(format!("Block {}: invalid sync header {}", i, header)),
+        }
+    }
+    (data, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joined(blocks: &[Block]) -> String {
+        blocks.iter().map(|block| block.bits.as_str()).collect()
+    }
+
+    #[test]
+    fn four_b_five_b_table() {
+        let expected = [
+            "11110", "01001", "10100", "10101", "01010", "01011", "01110", "01111", "10010", "10011", "10110", "10111", "11010",
+            "11011", "11100", "11101",
+        ];
+        for (value, code) in expected.iter().enumerate() {
+            let blocks = four_b_five_b(&format!("{:04b}", value), false);
+            assert_eq!((blocks[0].bits.as_str(), blocks[0].label.clone()), (*code, format!("{:X}", value)));
+            // No code group has more than one leading or two trailing zeros.
+            assert!(!code.starts_with("00") && !code.ends_with("000"));
+        }
+    }
+
+    #[test]
+    fn four_b_five_b_framing() {
+        let blocks = four_b_five_b("00011010", true);
+        let labels: Vec<&str> = blocks.iter().map(|block| block.label.as_str()).collect();
+        assert_eq!(labels, ["I", "J", "K", "1", "A", "T", "R", "I"]);
+        assert_eq!(joined(&blocks), ["11111", "11000", "10001", "01001", "10110", "01101", "00111", "11111"].concat());
+        assert!(blocks.iter().all(|block| block.control == (block.label.len() == 1 && !block.label.starts_with(['1', 'A']))));
+
+        let (data, errors) = four_b_five_b_decode(&joined(&blocks));
+        assert_eq!(data, "00011010");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn four_b_five_b_round_trip() {
+        let data: String = (0..256).map(|i| format!("{:08b}", i)).collect();
+        let (decoded, errors) = four_b_five_b_decode(&joined(&four_b_five_b(&data, false)));
+        assert_eq!(decoded, data);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn four_b_five_b_invalid_groups() {
+        // Only the 16 data and 5 control groups of the 32 are valid.
+        let valid: Vec<&str> = FOUR_B_FIVE_B.iter().copied().chain(FOUR_B_FIVE_B_CONTROL.iter().map(|&(code, _)| code)).collect();
+        for group in (0..32).map(|value| format!("{:05b}", value)) {
+            let (data, errors) = four_b_five_b_decode(&format!("11110{}", group));
+            if valid.contains(&group.as_str()) {
+                assert!(errors.is_empty(), "{}", group);
+            } else {
+                assert_eq!((data.as_str(), errors), ("0000", vec![format!("Group 1: invalid code group {}", group)]));
+            }
+        }
+    }
+
+    fn symbol(x: usize, y: usize, control: bool, mut disparity: i8) -> String {
+        eight_b_ten_b_symbol(x, y, control, &mut disparity).bits
+    }
+
+    #[test]
+    fn eight_b_ten_b_code_groups() {
+        assert_eq!(symbol(28, 5, true, -1), "0011111010");
+        assert_eq!(symbol(28, 5, true, 1), "1100000101");
+        assert_eq!(symbol(0, 0, false, -1), "1001110100");
+        assert_eq!(symbol(0, 0, false, 1), "0110001011");
+        assert_eq!(symbol(21, 5, false, -1), "1010101010");
+        assert_eq!(symbol(21, 5, false, 1), "1010101010");
+        assert_eq!(symbol(17, 7, false, -1), "1000110111");
+        assert_eq!(symbol(11, 7, false, 1), "1101001000");
+    }
+
+    #[test]
+    fn eight_b_ten_b_labels() {
+        let blocks = eight_b_ten_b("10110101", true);
+        let labels: Vec<&str> = blocks.iter().map(|block| block.label.as_str()).collect();
+        assert_eq!(labels, ["K28.5", "K27.7", "D21.5", "K29.7", "K23.7", "K28.5"]);
+    }
+
+    #[test]
+    fn eight_b_ten_b_round_trip_in_both_disparities() {
+        for byte in 0..256 {
+            // K28.5 flips the running disparity, so one of them leaves RD+ and two RD-.
+            for commas in [1, 2] {
+                let mut disparity = -1;
+                let mut bits = String::new();
+                for _ in 0..commas {
+                    bits.push_str(&eight_b_ten_b_symbol(28, 5, true, &mut disparity).bits);
+                }
+                assert_eq!(disparity, if commas == 1 { 1 } else { -1 });
+                bits.push_str(&eight_b_ten_b_symbol(byte & 0x1f, byte >> 5, false, &mut disparity).bits);
+
+                let (data, errors) = eight_b_ten_b_decode(&bits);
+                assert_eq!(data, format!("{:08b}", byte));
+                assert!(errors.is_empty(), "{:?}", errors);
+            }
+        }
+    }
+
+    #[test]
+    fn eight_b_ten_b_disparity_error() {
+        let (data, errors) = eight_b_ten_b_decode("0110001011");
+        assert_eq!(data, "00000000");
+        assert_eq!(errors, ["Symbol 0: disparity error in 6b sub-block 011000"]);
+    }
+
+    #[test]
+    fn eight_b_ten_b_misplaced_alternate_seven() {
+        // D1.7 leaves its 6b sub-block at RD+, where only P7 is allowed.
+        let (data, errors) = eight_b_ten_b_decode("0111011000");
+        assert_eq!(data, "11100001");
+        assert_eq!(errors, ["Symbol 0: D1.7 has to use P7 here"]);
+        // D17.7 at RD- needs A7, P7 would make a run of five.
+        let (_, errors) = eight_b_ten_b_decode("1000111110");
+        assert_eq!(errors, ["Symbol 0: D17.7 has to use A7 here"]);
+    }
+
+    fn bits(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:08b}", byte)).collect()
+    }
+
+    fn sample_bytes(count: usize) -> Vec<u8> {
+        (0..count).map(|i| (i * 37 + 11) as u8).collect()
+    }
+
+    #[test]
+    fn sixty_four_b_sixty_six_b_data_round_trip() {
+        for count in 1..=24 {
+            let data = bits(&sample_bytes(count));
+            let blocks = sixty_four_b_sixty_six_b(&data, false);
+            assert_eq!(blocks.len(), count.div_ceil(8));
+            assert!(blocks.iter().all(|block| block.bits.starts_with(DATA_HEADER) && block.bits.len() == 66));
+
+            let line: String = blocks.iter().map(|block| block.bits.as_str()).collect();
+            let (decoded, errors) = sixty_four_b_sixty_six_b_decode(&line);
+            assert!(errors.is_empty(), "{:?}", errors);
+            assert_eq!(&decoded[..data.len()], data);
+            assert!(decoded[data.len()..].chars().all(|c| c == '0'));
+        }
+    }
+
+    #[test]
+    fn sixty_four_b_sixty_six_b_control_blocks() {
+        let terminate = [0x87, 0x99, 0xaa, 0xb4, 0xcc, 0xd2, 0xe1, 0xff];
+        for count in 0..=31 {
+            let bytes = sample_bytes(count);
+            let blocks = sixty_four_b_sixty_six_b(&bits(&bytes), true);
+            let labels: Vec<&str> = blocks.iter().map(|block| block.label.as_str()).collect();
+            let left = count.saturating_sub(7) % 8;
+            let mut expected = vec!["Type 0x1E".to_string(), "Type 0x78".to_string()];
+            expected.extend(std::iter::repeat_n("Data".to_string(), count.saturating_sub(7) / 8));
+            expected.push(format!("Type 0x{:02X}", terminate[left]));
+            expected.push("Type 0x1E".to_string());
+            assert_eq!(labels, expected, "{} byte(s)", count);
+            for block in &blocks {
+                let header = if block.control { CONTROL_HEADER } else { DATA_HEADER };
+                assert!(block.bits.starts_with(header));
+            }
+
+            let line: String = blocks.iter().map(|block| block.bits.as_str()).collect();
+            let (decoded, errors) = sixty_four_b_sixty_six_b_decode(&line);
+            assert!(errors.is_empty(), "{:?}", errors);
+            let mut padded = bytes.clone();
+            padded.resize(count.max(7), 0);
+            assert_eq!(decoded, bits(&padded), "{} byte(s)", count);
+        }
+    }
+
+    #[test]
+    fn sixty_four_b_sixty_six_b_bad_header() {
+        let mut line = sixty_four_b_sixty_six_b(&bits(&sample_bytes(8)), false)[0].bits.clone();
+        line.replace_range(..2, "11");
+        let (_, errors) = sixty_four_b_sixty_six_b_decode(&line);
+        assert_eq!(errors, ["Block 0: invalid sync header 11"]);
+    }
+}
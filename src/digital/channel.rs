@@ -0,0 +1,123 @@
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+
+/// Channel that adds white Gaussian noise to the line signal.
+pub struct Channel {
+    pub enabled: bool,
+    /// Energy per bit over the noise power spectral density, in dB.
+    pub eb_n0: f32,
+    pub seed: u64,
+    /// Samples taken of every signal element.
+    pub samples: usize,
+}
+
+impl Channel {
+    /// Holds every level for `samples` samples.
+    pub fn oversample(&self, levels: &[i8]) -> Vec<f32> {
+        levels
+            .iter()
+            .flat_map(|&level| std::iter::repeat_n(level as f32, self.samples))
+            .collect()
+    }
+
+    /// Standard deviation of the noise on every sample, N0 / 2 per sample with Eb
+    /// measured on `clean`, where each element carries `bits_per_element` bits.
+    pub fn sigma(&self, clean: &[f32], bits_per_element: f32) -> f32 {
+        if clean.is_empty() {
+            return 0.0;
+        }
+        let power = clean.iter().map(|x| x * x).sum::<f32>() / clean.len() as f32;
+        let eb = power * self.samples as f32 / bits_per_element;
+        let n0 = eb / 10f32.powf(self.eb_n0 / 10.0);
+        (n0 / 2.0).sqrt()
+    }
+
+    /// Adds noise drawn from the seed, so the same settings always give the same
+    /// waveform.
+    pub fn transmit(&self, clean: &[f32], bits_per_element: f32) -> Vec<f32> {
+        let sigma = self.sigma(clean, bits_per_element);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        clean.iter().map(|x| x + sigma * gaussian(&mut rng)).collect()
+    }
+}
+
+/// Standard normal sample by the Box-Muller transform.
+pub fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u: f32 = 1.0 - rng.gen::<f32>();
+    let v: f32 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f32::consts::PI * v).cos()
+}
+
+/// Averages the samples of every element, the matched filter for the rectangular
+/// pulses of the line codes.
+pub fn integrate(waveform: &[f32], samples: usize) -> Vec<f32> {
+    waveform
+        .chunks(samples)
+        .map(|element| element.iter().sum::<f32>() / element.len() as f32)
+        .collect()
+}
+
+/// Decides every value for the nearest of `levels`.
+pub fn slice(values: &[f32], levels: &[i8]) -> Vec<i8> {
+    values
+        .iter()
+        .map(|&x| {
+            levels
+                .iter()
+                .copied()
+                .min_by(|&a, &b| (a as f32 - x).abs().total_cmp(&(b as f32 - x).abs()))
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(eb_n0: f32) -> Channel {
+        Channel {
+            enabled: true,
+            eb_n0,
+            seed: 23,
+            samples: 4,
+        }
+    }
+
+    #[test]
+    fn sigma_from_eb_n0() {
+        // Unit power held for 4 samples gives Eb = 4, so N0 = 0.4 at 10 dB.
+        let clean = channel(10.0).oversample(&[1, -1, -1, 1]);
+        assert!((channel(10.0).sigma(&clean, 1.0) - 0.2f32.sqrt()).abs() < 1e-6);
+        // Two bits per element halve Eb and with it the noise power.
+        assert!((channel(10.0).sigma(&clean, 2.0) - 0.1f32.sqrt()).abs() < 1e-6);
+        assert_eq!(channel(10.0).sigma(&[], 1.0), 0.0);
+    }
+
+    #[test]
+    fn noise_variance_matches_sigma() {
+        for eb_n0 in [0.0, 6.0, 12.0] {
+            let channel = channel(eb_n0);
+            let levels: Vec<i8> = (0..25_000).map(|i| [-3, -1, 1, 3][i % 4]).collect();
+            let clean = channel.oversample(&levels);
+            let sigma = channel.sigma(&clean, 2.0);
+            let noise: Vec<f32> = channel.transmit(&clean, 2.0).iter().zip(&clean).map(|(x, c)| x - c).collect();
+            let mean = noise.iter().sum::<f32>() / noise.len() as f32;
+            let variance = noise.iter().map(|n| (n - mean).powi(2)).sum::<f32>() / noise.len() as f32;
+            assert!(mean.abs() < 0.02 * sigma, "{} dB: mean {}", eb_n0, mean);
+            assert!((variance / (sigma * sigma) - 1.0).abs() < 0.02, "{} dB: {} against {}", eb_n0, variance, sigma * sigma);
+            assert_eq!(channel.transmit(&clean, 2.0)[..100], channel.transmit(&clean, 2.0)[..100]);
+        }
+    }
+
+    #[test]
+    fn clean_levels_come_back() {
+        let levels: Vec<i8> = (0..50).map(|i| [-3, 1, 3, -1, 1][i % 5]).collect();
+        for samples in [1, 4, 7] {
+            let channel = Channel { samples, ..channel(0.0) };
+            let received = slice(&integrate(&channel.oversample(&levels), samples), &[-3, -1, 1, 3]);
+            assert_eq!(received, levels);
+        }
+        assert_eq!(slice(&[0.4, -0.6, 5.0, -1.9], &[-1, 0, 1]), [0, -1, 1, -1]);
+    }
+}
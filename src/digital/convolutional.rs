@@ -0,0 +1,266 @@
+use super::fec::Correction;
+use std::fmt::{self, Debug};
+
+/// Rate-1/2 convolutional code. Both generators are in the usual octal notation,
+/// the most significant of their `constraint` bits taking the current input.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Convolutional {
+    pub constraint: usize,
+    pub generators: [u32; 2],
+    /// Decode from the received levels rather than from decided bits.
+    pub soft: bool,
+}
+
+impl Debug for Convolutional {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "K={} ({:o}, {:o})", self.constraint, self.generators[0], self.generators[1])
+    }
+}
+
+impl Default for Convolutional {
+    /// The K=3 code with generators 7 and 5.
+    fn default() -> Self {
+        Convolutional {
+            constraint: 3,
+            generators: [0o7, 0o5],
+            soft: false,
+        }
+    }
+}
+
+impl Convolutional {
+    /// Parses two octal generators such as "171, 133" that fit the constraint
+    /// length.
+    pub fn parse_generators(generators: &str, constraint: usize) -> Option<[u32; 2]> {
+        let parsed: Vec<u32> = generators
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|generator| !generator.is_empty())
+            .map(|generator| u32::from_str_radix(generator, 8).ok())
+            .collect::<Option<_>>()?;
+        match parsed[..] {
+            [a, b] if [a, b].iter().all(|&g| g > 0 && g < 1 << constraint) => Some([a, b]),
+            _ => None,
+        }
+    }
+
+    /// The generators of the best known rate-1/2 code of every constraint length
+    /// from 2 to 9, the ones of NASA and 802.11 for K=7 among them.
+    pub fn standard_generators(constraint: usize) -> [u32; 2] {
+        match constraint {
+            2 => [0o3, 0o1],
+            3 => [0o7, 0o5],
+            4 => [0o17, 0o15],
+            5 => [0o23, 0o35],
+            6 => [0o53, 0o75],
+            7 => [0o171, 0o133],
+            8 => [0o247, 0o371],
+            _ => [0o561, 0o753],
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        1 << (self.constraint - 1)
+    }
+
+    /// The two output bits for `input` leaving `state`, which holds the previous
+    /// inputs with the most recent one in its top bit, and the state entered.
+    pub fn branch(&self, state: usize, input: bool) -> ([bool; 2], usize) {
+        let register = ((input as usize) << (self.constraint - 1)) | state;
+        let output = self.generators.map(|g| (register as u32 & g).count_ones() % 2 == 1);
+        (output, register >> 1)
+    }
+
+    /// Encodes `data` followed by `constraint - 1` zeros that bring the encoder
+    /// back to the all-zero state.
+    pub fn encode(&self, data: &str) -> String {
+        let tail = std::iter::repeat_n(false, self.constraint - 1);
+        let mut state = 0;
+        let mut coded = String::with_capacity(2 * (data.len() + self.constraint - 1));
+        for input in data.chars().map(|c| c == '1').chain(tail) {
+            let (output, next) = self.branch(state, input);
+            coded.extend(output.iter().map(|&bit| if bit { '1' } else { '0' }));
+            state = next;
+        }
+        coded
+    }
+
+    /// Viterbi decoder over a terminated trellis. `received` holds one value per
+    /// coded bit, from -1 for a certain zero to 1 for a certain one, and a zero
+    /// for an erasure, whose sign still carries the bit that was decided. Hard
+    /// decisions count differing bits, leaving erasures out, and soft decisions
+    /// add up squared distances. The states of the surviving path are returned
+    /// in `Correction::trellis`.
+    pub fn decode(&self, received: &[f32]) -> Correction {
+        let mut correction = Correction::default();
+        if !received.len().is_multiple_of(2) {
+            correction.errors.push("Odd number of coded bits".to_string());
+        }
+        let steps = received.len() / 2;
+        if steps < self.constraint - 1 {
+            correction.errors.push("Too few coded bits for the tail".to_string());
+            return correction;
+        }
+
+        let level = |bit: bool| if bit { 1.0 } else { -1.0 };
+        let metric = |value: f32, bit: bool| {
+            if self.soft {
+                (value - level(bit)).powi(2)
+            } else if value == 0.0 || (value > 0.0) == bit {
+                0.0
+            } else {
+                1.0
+            }
+        };
+
+        let mut metrics = vec![f32::INFINITY; self.states()];
+        metrics[0] = 0.0;
+        let mut survivors = Vec::with_capacity(steps);
+        for pair in received.chunks_exact(2) {
+            let mut next_metrics = vec![f32::INFINITY; self.states()];
+            let mut previous = vec![(0, false); self.states()];
+            for state in (0..self.states()).filter(|&state| metrics[state].is_finite()) {
+                for input in [false, true] {
+                    let (output, next) = self.branch(state, input);
+                    let candidate = metrics[state] + metric(pair[0], output[0]) + metric(pair[1], output[1]);
+                    if candidate < next_metrics[next] {
+                        next_metrics[next] = candidate;
+                        previous[next] = (state, input);
+                    }
+                }
+            }
+            survivors.push(previous);
+            metrics = next_metrics;
+        }
+
+        let mut state = 0;
+        let mut path = vec![state];
+        let mut inputs = Vec::with_capacity(steps);
+        for previous in survivors.iter().rev() {
+            let (from, input) = previous[state];
+            inputs.push(input);
+            state = from;
+            path.push(state);
+        }
+        inputs.reverse();
+        path.reverse();
+
+        let mut data: String = inputs.iter().map(|&bit| if bit { '1' } else { '0' }).collect();
+        let reencoded = self.encode(&data[..steps + 1 - self.constraint]);
+        for (i, (bit, &value)) in reencoded.chars().zip(received).enumerate() {
+            if (bit == '1') != value.is_sign_positive() {
+                correction.corrected.push(format!("Corrected coded bit {}", i));
+            }
+        }
+        data.truncate(steps + 1 - self.constraint);
+        correction.data = data;
+        correction.trellis = path;
+        correction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    fn levels(coded: &str) -> Vec<f32> {
+        coded.chars().map(|c| if c == '1' { 1.0 } else { -1.0 }).collect()
+    }
+
+    fn code(constraint: usize, generators: [u32; 2], soft: bool) -> Convolutional {
+        Convolutional { constraint, generators, soft }
+    }
+
+    #[test]
+    fn textbook_encoding() {
+        assert_eq!(Convolutional::default().encode("1011"), "111000010111");
+        assert_eq!(Convolutional::parse_generators("171, 133", 7), Some([0o171, 0o133]));
+        assert_eq!(Convolutional::parse_generators("171, 133", 6), None);
+        for constraint in 2..=9 {
+            let [a, b] = Convolutional::standard_generators(constraint);
+            let text = format!("{:o}, {:o}", a, b);
+            assert_eq!(Convolutional::parse_generators(&text, constraint), Some([a, b]));
+        }
+    }
+
+    #[test]
+    fn trellis_follows_the_encoder() {
+        let code = Convolutional::default();
+        let correction = code.decode(&levels(&code.encode("1011")));
+        assert_eq!(correction.data, "1011");
+        assert_eq!(correction.trellis, [0, 2, 1, 2, 3, 1, 0]);
+        assert!(correction.corrected.is_empty() && correction.errors.is_empty());
+    }
+
+    #[test]
+    fn corrects_spread_out_flips() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for (constraint, generators, spacing) in [(3, [0o7, 0o5], 12), (7, [0o171, 0o133], 40)] {
+            for soft in [false, true] {
+                let code = code(constraint, generators, soft);
+                for _ in 0..20 {
+                    let data: String = (0..200).map(|_| if rng.gen_bool(0.5) { '1' } else { '0' }).collect();
+                    let mut received = levels(&code.encode(&data));
+                    // One flip somewhere in every stretch of `spacing` coded bits,
+                    // well within the free distance of both codes.
+                    let flips: Vec<usize> = (0..received.len() / spacing).map(|i| i * spacing + rng.gen_range(0..spacing / 2)).collect();
+                    for &i in &flips {
+                        received[i] = -received[i];
+                    }
+                    let correction = code.decode(&received);
+                    assert_eq!(correction.data, data, "{:?}, soft {}", code, soft);
+                    assert_eq!(correction.corrected.len(), flips.len(), "{:?}, soft {}", code, soft);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn soft_decisions_weigh_reliability() {
+        let hard = code(3, [0o7, 0o5], false);
+        let soft = code(3, [0o7, 0o5], true);
+        let data = "1011001110";
+        let mut received = levels(&hard.encode(data));
+        // Three weak values against the sent bits in a row are too many for hard
+        // decisions but cost little next to the confident ones.
+        for value in &mut received[4..7] {
+            *value *= -0.1;
+        }
+        assert_eq!(soft.decode(&received).data, data);
+        assert_ne!(hard.decode(&received).data, data);
+    }
+
+    #[test]
+    fn erasures_cost_nothing() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for soft in [false, true] {
+            let code = code(3, [0o7, 0o5], soft);
+            let data = "1111";
+            let mut received = levels(&code.encode(data));
+            // An erasure keeps the sign of the bit decided there, which was right.
+            received[3] *= 0.0;
+            let correction = code.decode(&received);
+            assert_eq!(correction.data, data);
+            assert!(correction.corrected.is_empty(), "soft {}: {:?}", soft, correction.corrected);
+
+            // Erasures in every other pair and a flip in between.
+            let data: String = (0..100).map(|_| if rng.gen_bool(0.5) { '1' } else { '0' }).collect();
+            let mut received = levels(&code.encode(&data));
+            for i in (0..received.len()).step_by(16) {
+                received[i] *= 0.0;
+                received[i + 8] = -received[i + 8];
+            }
+            let correction = code.decode(&received);
+            assert_eq!(correction.data, data, "soft {}", soft);
+            assert_eq!(correction.corrected.len(), received.len().div_ceil(16), "soft {}", soft);
+        }
+    }
+
+    #[test]
+    fn reports_malformed_input() {
+        let code = Convolutional::default();
+        assert!(!code.decode(&[1.0, 1.0, -1.0]).errors.is_empty());
+        assert!(!code.decode(&[1.0]).errors.is_empty());
+    }
+}
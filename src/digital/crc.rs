@@ -0,0 +1,226 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrcKind {
+    CRC8,
+    CRC16CCITTFalse,
+    CRC32,
+    Custom,
+    None,
+}
+
+/// A cyclic redundancy check computed by long division of the bits, in the order
+/// they are entered, by `generator`. `init` is added to the first `width` bits of
+/// the dividend and `xor_out` to the remainder. Reflected CRCs take every whole
+/// byte least significant bit first and reverse the remainder, which is how the
+/// byte-wise CRC-32 of Ethernet and zip sees the data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crc {
+    pub width: usize,
+    /// Generator polynomial including its x^width term.
+    pub generator: u64,
+    pub init: u64,
+    pub xor_out: u64,
+    pub reflect: bool,
+}
+
+pub const CRC8: Crc = Crc {
+    width: 8,
+    generator: 0x107,
+    init: 0,
+    xor_out: 0,
+    reflect: false,
+};
+
+/// The unreflected CRC-16 with all ones preset, often just called CRC-16-CCITT.
+pub const CRC16_CCITT_FALSE: Crc = Crc {
+    width: 16,
+    generator: 0x11021,
+    init: 0xffff,
+    xor_out: 0,
+    reflect: false,
+};
+
+pub const CRC32: Crc = Crc {
+    width: 32,
+    generator: 0x104c11db7,
+    init: 0xffffffff,
+    xor_out: 0xffffffff,
+    reflect: true,
+};
+
+/// The HDLC frame check sequence of ITU-T X.25. The frame is given in the order
+/// its bits are sent, so no reflection is needed.
+pub const X25: Crc = Crc {
+    width: 16,
+    generator: 0x11021,
+    init: 0xffff,
+    xor_out: 0xffff,
+    reflect: false,
+};
+
+impl CrcKind {
+    pub fn crc(&self, custom: Crc) -> Option<Crc> {
+        match self {
+            CrcKind::CRC8 => Some(CRC8),
+            CrcKind::CRC16CCITTFalse => Some(CRC16_CCITT_FALSE),
+            CrcKind::CRC32 => Some(CRC32),
+            CrcKind::Custom => Some(custom),
+            CrcKind::None => None,
+        }
+    }
+}
+
+fn bits_of(value: u64, width: usize) -> String {
+    (0..width)
+        .rev()
+        .map(|i| if (value >> i) & 1 == 1 { '1' } else { '0' })
+        .collect()
+}
+
+impl Crc {
+    /// Parses a generator such as "x^3 + x + 1" into a plain CRC without initial
+    /// value or final XOR.
+    pub fn parse(polynomial: &str) -> Option<Crc> {
+        let mut generator = 0u64;
+        for term in polynomial.split('+').map(|term| term.trim()) {
+            let exponent: u32 = match term {
+                "1" => 0,
+                "x" => 1,
+                _ => term.strip_prefix("x^")?.parse().ok()?,
+            };
+            if exponent > 63 {
+                return None;
+            }
+            generator |= 1 << exponent;
+        }
+        let width = 63 - generator.leading_zeros() as usize;
+        if generator == 0 || width == 0 {
+            return None;
+        }
+        Some(Crc {
+            width,
+            generator,
+            init: 0,
+            xor_out: 0,
+            reflect: false,
+        })
+    }
+
+    /// The message bits as they enter the division, every whole byte reversed
+    /// for a reflected CRC.
+    fn dividend(&self, bits: &str) -> Vec<u8> {
+        let mut dividend: Vec<u8> = bits.bytes().map(|b| b - b'0').collect();
+        if self.reflect {
+            dividend.chunks_exact_mut(8).for_each(|byte| byte.reverse());
+        }
+        dividend
+    }
+
+    /// Writes out the long division of the bits followed by `width` zeros for the
+    /// first `lines` lines: the dividend, then for every subtraction the divisor
+    /// lined up under it and the dividend left over. Returns the lines with the
+    /// number there would be in all.
+    pub fn divide(&self, bits: &str, lines: usize) -> (Vec<String>, usize) {
+        let mut dividend = self.dividend(bits);
+        dividend.extend(std::iter::repeat_n(0, self.width));
+        for (i, bit) in bits_of(self.init, self.width).bytes().enumerate() {
+            dividend[i] ^= bit - b'0';
+        }
+
+        let text = |dividend: &[u8]| dividend.iter().map(|&b| (b + b'0') as char).collect::<String>();
+        let divisor = bits_of(self.generator, self.width + 1);
+        let mut steps = vec![text(&dividend)];
+        let mut total = 1;
+        for i in 0..bits.len() {
+            if dividend[i] == 0 {
+                continue;
+            }
+            for (d, g) in dividend[i..].iter_mut().zip(divisor.bytes()) {
+                *d ^= g - b'0';
+            }
+            total += 2;
+            if steps.len() < lines {
+                steps.push(format!("{}{}", " ".repeat(i), divisor));
+                steps.push(text(&dividend));
+            }
+        }
+        steps.truncate(lines);
+        (steps, total)
+    }
+
+    /// The remainder of the same division from a shift register, which takes
+    /// the bits one at a time and subtracts the generator whenever a one drops
+    /// out of the top, with `init` preset into the register.
+    pub fn checksum(&self, bits: &str) -> String {
+        let mask = (1 << self.width) - 1;
+        let mut register = self.init & mask;
+        for bit in self.dividend(bits) {
+            let top = (register >> (self.width - 1)) & 1;
+            register = (register << 1) & mask;
+            if top ^ bit as u64 == 1 {
+                register ^= self.generator & mask;
+            }
+        }
+        if self.reflect {
+            register = register.reverse_bits() >> (64 - self.width);
+        }
+        bits_of(register ^ self.xor_out, self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bytes of "123456789", on which every CRC catalogue gives its check value.
+    fn check_bits() -> String {
+        "123456789".bytes().map(|byte| format!("{:08b}", byte)).collect()
+    }
+
+    fn check(crc: Crc) -> u64 {
+        u64::from_str_radix(&crc.checksum(&check_bits()), 2).unwrap()
+    }
+
+    #[test]
+    fn check_values() {
+        assert_eq!(check(CRC8), 0xf4);
+        assert_eq!(check(CRC16_CCITT_FALSE), 0x29b1);
+        assert_eq!(check(CRC32), 0xcbf43926);
+        // CRC-16/GENIBUS, the X.25 FCS with the bits in the order they are sent.
+        assert_eq!(check(X25), 0xd64e);
+    }
+
+    #[test]
+    fn parsed_generator() {
+        let crc = Crc::parse("x^3 + x + 1").unwrap();
+        assert_eq!((crc.width, crc.generator), (3, 0b1011));
+        assert_eq!(crc.checksum("11010011101100"), "100");
+        assert_eq!(Crc::parse("1"), None);
+        assert_eq!(Crc::parse("x^64 + 1"), None);
+    }
+
+    #[test]
+    fn register_matches_long_division() {
+        let crc = Crc {
+            init: 0b101,
+            ..Crc::parse("x^6 + x^4 + x + 1").unwrap()
+        };
+        for len in 0..40 {
+            let bits: String = (0..len).map(|i| if (i * i + len) % 3 == 0 { '1' } else { '0' }).collect();
+            let (steps, total) = crc.divide(&bits, usize::MAX);
+            assert_eq!(steps.len(), total);
+            let last = steps.last().unwrap();
+            assert_eq!(crc.checksum(&bits), last[last.len() - crc.width..], "{}", bits);
+        }
+        let (all, _) = crc.divide("1101001110110011", usize::MAX);
+        let (steps, total) = crc.divide("1101001110110011", 5);
+        assert_eq!((&steps[..], total), (&all[..5], all.len()));
+    }
+
+    #[test]
+    fn appended_checksum_leaves_no_remainder() {
+        let crc = Crc::parse("x^8 + x^2 + x + 1").unwrap();
+        let message = "1011001110001";
+        let checked = format!("{}{}", message, crc.checksum(message));
+        assert_eq!(crc.checksum(&checked), "00000000");
+    }
+}
@@ -0,0 +1,311 @@
+use super::encoding::*;
+use super::scramble::{Scramble, Scrambling};
+
+/// Turns the levels of a line code back into the bits that produced them.
+pub trait Decode {
+    fn decode(&self, encoded: &[i8], scrambling: Scrambling) -> String;
+
+    /// Whether `soft_decode` gives values, which it does for the codes whose bits
+    /// can be told apart by the sign of a single value.
+    fn has_soft_output(&self) -> bool {
+        false
+    }
+
+    /// A value from -1 to 1 for every bit, from the matched filter output of each
+    /// element, for the codes with a soft output.
+    fn soft_decode(&self, _filtered: &[f32]) -> Option<Vec<f32>> {
+        None
+    }
+}
+
+fn bit(one: bool) -> char {
+    if one {
+        '1'
+    } else {
+        '0'
+    }
+}
+
+impl Decode for NRZL {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.iter().map(|&x| bit(x > 0)).collect()
+    }
+
+    fn has_soft_output(&self) -> bool {
+        true
+    }
+
+    fn soft_decode(&self, filtered: &[f32]) -> Option<Vec<f32>> {
+        Some(filtered.iter().map(|x| x.clamp(-1.0, 1.0)).collect())
+    }
+}
+
+impl Decode for NRZI {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        let mut previous = 1;
+        encoded
+            .iter()
+            .map(|&x| {
+                let changed = x != previous;
+                previous = x;
+                bit(changed)
+            })
+            .collect()
+    }
+}
+
+/// Both decisions read the first half of the bit only, as the theoretical BER
+/// assumes, so hard and soft decoding see the same signal.
+impl Decode for Manchester {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half[0] < 0)).collect()
+    }
+
+    fn has_soft_output(&self) -> bool {
+        true
+    }
+
+    fn soft_decode(&self, filtered: &[f32]) -> Option<Vec<f32>> {
+        Some(filtered.chunks_exact(2).map(|half| (-half[0]).clamp(-1.0, 1.0)).collect())
+    }
+}
+
+impl Decode for ManchesterDifferential {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        let mut previous = 1;
+        encoded
+            .chunks(2)
+            .map(|half| {
+                let changed = half[0] != previous;
+                previous = half[0];
+                bit(changed)
+            })
+            .collect()
+    }
+}
+
+impl Decode for Miller {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half.first() != half.last())).collect()
+    }
+}
+
+impl Decode for BiphaseMark {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half.first() != half.last())).collect()
+    }
+}
+
+impl Decode for BiphaseSpace {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half.first() == half.last())).collect()
+    }
+}
+
+impl Decode for CMI {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half.first() == half.last())).collect()
+    }
+}
+
+impl Decode for AMI {
+    fn decode(&self, encoded: &[i8], scrambling: Scrambling) -> String {
+        self.descramble(encoded, scrambling)
+    }
+}
+
+impl Decode for Pseudoternary {
+    fn decode(&self, encoded: &[i8], scrambling: Scrambling) -> String {
+        self.descramble(encoded, scrambling)
+    }
+}
+
+impl Decode for MLT3 {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        let mut previous = 0;
+        encoded
+            .iter()
+            .map(|&x| {
+                let changed = x != previous;
+                previous = x;
+                bit(changed)
+            })
+            .collect()
+    }
+}
+
+impl Decode for TwoB1Q {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded
+            .iter()
+            .map(|&x| match x {
+                x if x <= -2 => "00",
+                x if x < 0 => "01",
+                x if x < 2 => "11",
+                _ => "10",
+            })
+            .collect()
+    }
+}
+
+impl Decode for PAM {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        let bits = self.bits_per_symbol();
+        encoded
+            .iter()
+            .map(|&x| {
+                let index = self.index(x);
+                let value = if self.gray { index ^ (index >> 1) } else { index };
+                format!("{:0width$b}", value, width = bits)
+            })
+            .collect()
+    }
+}
+
+/// Undoes `partial_response`. Precoded levels are decided one by one, otherwise
+/// every decision feeds back into the next one.
+fn partial_response_decode(encoded: &[i8], delay: usize, sign: i8, precoder: bool) -> String {
+    if precoder {
+        return encoded.iter().map(|&y| bit((y == 0) == (sign > 0))).collect();
+    }
+
+    let mut sent = vec![-1; delay];
+    encoded
+        .iter()
+        .map(|&y| {
+            let previous = sent[sent.len() - delay];
+            let level = if 2 * y - sign * previous > 0 { 1 } else { -1 };
+            sent.push(level);
+            bit(level > 0)
+        })
+        .collect()
+}
+
+impl Decode for Duobinary {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        partial_response_decode(encoded, 1, 1, self.precoder)
+    }
+}
+
+impl Decode for ModifiedDuobinary {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        partial_response_decode(encoded, 2, -1, self.precoder)
+    }
+}
+
+impl Decode for UnipolarRZ {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half[0] > 0)).collect()
+    }
+}
+
+impl Decode for PolarRZ {
+    fn decode(&self, encoded: &[i8], _scrambling: Scrambling) -> String {
+        encoded.chunks(2).map(|half| bit(half[0] > 0)).collect()
+    }
+
+    fn has_soft_output(&self) -> bool {
+        true
+    }
+
+    fn soft_decode(&self, filtered: &[f32]) -> Option<Vec<f32>> {
+        Some(filtered.chunks(2).map(|half| half[0].clamp(-1.0, 1.0)).collect())
+    }
+}
+
+impl Decode for BipolarRZ {
+    fn decode(&self, encoded: &[i8], scrambling: Scrambling) -> String {
+        let pulses: Vec<i8> = encoded.chunks(2).map(|half| half[0]).collect();
+        self.descramble(&pulses, scrambling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::channel::slice;
+    use super::super::scramble::{violations, Scrambling, Violation};
+    use super::super::encoding::{Encode, PAM};
+    use super::Decode;
+    use super::super::{encoding, ENCODINGS};
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    #[test]
+    fn every_line_code_round_trips() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let schemes = [Scrambling::None, Scrambling::B3ZS, Scrambling::B6ZS, Scrambling::B8ZS, Scrambling::HDB3];
+        for name in ENCODINGS {
+            let code = encoding(name).unwrap();
+            for scheme in schemes {
+                for i in 0..20 {
+                    // Mostly zeros or mostly ones, so that the long runs of spaces the
+                    // substitutions replace turn up for AMI and pseudoternary alike.
+                    let ones = if i % 2 == 0 { 0.2 } else { 0.8 };
+                    let len = rng.gen_range(1..200);
+                    let bits: String = (0..len).map(|_| if rng.gen_bool(ones) { '1' } else { '0' }).collect();
+                    let encoded = code.encode(&bits, scheme);
+                    let mut decoded = code.decode(&encoded, scheme);
+                    decoded.truncate(len);
+                    assert_eq!(decoded, bits, "{} with {:?}", name, scheme);
+
+                    if code.is_bipolar() {
+                        let pulses: Vec<i8> = encoded.iter().step_by(code.elements_per_symbol()).copied().collect();
+                        let found = violations(&pulses, scheme);
+                        assert!(
+                            found.iter().all(|&(_, kind)| kind != Violation::Error),
+                            "{} with {:?} on {}",
+                            name,
+                            scheme,
+                            bits
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn soft_output_is_announced() {
+        for name in ENCODINGS {
+            let code = encoding(name).unwrap();
+            let soft = code.soft_decode(&[0.5, -0.5, 0.25, 1.5]);
+            assert_eq!(code.has_soft_output(), soft.is_some(), "{}", name);
+            assert!(soft.unwrap_or_default().iter().all(|value| value.abs() <= 1.0), "{}", name);
+        }
+    }
+
+    #[test]
+    fn soft_and_hard_decisions_agree() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for name in ENCODINGS {
+            let code = encoding(name).unwrap();
+            if !code.has_soft_output() {
+                continue;
+            }
+            // Clear of the zero level, which the hard slicer of polar RZ also knows.
+            let filtered: Vec<f32> = (0..200).map(|_| rng.gen_range(0.6..1.5) * if rng.gen() { 1.0 } else { -1.0 }).collect();
+            let hard = code.decode(&slice(&filtered, &code.levels()), Scrambling::None);
+            let soft = code.soft_decode(&filtered).unwrap();
+            for (i, (decided, value)) in hard.chars().zip(soft).enumerate() {
+                assert_eq!(decided == '1', value > 0.0, "{} bit {}", name, i);
+            }
+        }
+    }
+
+    #[test]
+    fn gray_mapped_pam_levels_differ_in_one_bit() {
+        for order in [4, 8, 16] {
+            let code = PAM { order, gray: true };
+            let levels = code.levels();
+            assert_eq!(levels.len(), order);
+            let bits: Vec<String> = levels.iter().map(|&level| code.decode(&[level], Scrambling::None)).collect();
+            for (level, bits) in levels.iter().zip(&bits) {
+                assert_eq!(code.encode(bits, Scrambling::None), [*level], "PAM-{} {}", order, bits);
+            }
+            for pair in bits.windows(2) {
+                let differing = pair[0].chars().zip(pair[1].chars()).filter(|(a, b)| a != b).count();
+                assert_eq!(differing, 1, "PAM-{}: {} next to {}", order, pair[0], pair[1]);
+            }
+        }
+    }
+}
@@ -1,26 +1,101 @@
+use super::decoding::Decode;
+use super::math::q;
 use super::scramble::{Scramble, Scrambling};
-use super::DigitalSettings;
-use nannou::prelude::{pt2, App, Draw, STEELBLUE};
-use std::fmt::Debug;
+use nannou::prelude::{pt2, rgba, App, Draw, LIGHTGRAY, STEELBLUE};
+use nannou_egui::egui;
+use std::fmt::{self, Debug};
 
-pub trait Encode: Debug {
-    fn draw_encoding(&self, encoded: &Vec<i8>, app: &App, draw: &Draw) {
+const MAX_HEIGHT: f32 = 150.0;
+
+pub trait Encode: Debug + Decode {
+    fn draw_encoding(&self, encoded: &[i8], app: &App, draw: &Draw) {
         let window = app.main_window();
         let win = window.rect();
         let width = win.w();
 
-        let bit_length = width / encoded.len() as f32;
+        let element_length = width / encoded.len() as f32;
+        let level_height = self.level_height();
         let points = encoded.iter().enumerate().flat_map(|(i, &x)| {
-            let height = x as f32 * 50.0;
-            let start = pt2(win.left() + bit_length * i as f32, height);
-            let end = pt2(win.left() + bit_length * (i + 1) as f32, height);
+            let height = x as f32 * level_height;
+            let start = pt2(win.left() + element_length * i as f32, height);
+            let end = pt2(win.left() + element_length * (i + 1) as f32, height);
             [(start, STEELBLUE), (end, STEELBLUE)]
         });
 
+        // Mark the symbol cells so that half-bit elements stay readable.
+        let symbol_length = element_length * self.elements_per_symbol() as f32;
+        for i in 0..encoded.len() / self.elements_per_symbol() {
+            let x = win.left() + symbol_length * i as f32;
+            draw.line()
+                .weight(1.0)
+                .points(pt2(x, -10.0), pt2(x, 10.0))
+                .color(LIGHTGRAY);
+        }
+
+        // Guide line for every level the code can take.
+        for level in self.levels() {
+            let y = level as f32 * level_height;
+            draw.line()
+                .weight(1.0)
+                .points(pt2(win.left(), y), pt2(win.right(), y))
+                .color(rgba(1.0, 1.0, 1.0, 0.3));
+            draw.text(&level.to_string())
+                .x_y(win.left() + 10.0, y + 8.0)
+                .font_size(10)
+                .color(LIGHTGRAY);
+        }
+
         draw.polyline().weight(4.0).points_colored(points);
     }
 
-    fn encode(&self, settings: &DigitalSettings) -> Vec<i8>;
+    /// Number of bits carried by one symbol, e.g. 2 for the dibits of 2B1Q.
+    fn bits_per_symbol(&self) -> usize {
+        1
+    }
+
+    /// Number of signal elements produced for every symbol, e.g. 2 for the half-bit codes.
+    fn elements_per_symbol(&self) -> usize {
+        1
+    }
+
+    /// Every level the code produces, from lowest to highest.
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 1]
+    }
+
+    /// Height of one level step on the canvas, so the largest level stays in view.
+    fn level_height(&self) -> f32 {
+        let max_level = self.levels().iter().map(|level| level.abs()).max().unwrap_or(1).max(1);
+        (MAX_HEIGHT / max_level as f32).min(50.0)
+    }
+
+    /// Number of zero bits appended to `len` input bits to fill the last symbol.
+    fn padding(&self, len: usize) -> usize {
+        (self.bits_per_symbol() - len % self.bits_per_symbol()) % self.bits_per_symbol()
+    }
+
+    /// Whether the code alternates pulse polarity, which is what makes it
+    /// eligible for B8ZS/HDB3 substitution.
+    fn is_bipolar(&self) -> bool {
+        false
+    }
+
+    /// Bit error rate of the code at `eb_n0` (as a ratio, not in dB) with a
+    /// matched filter and a decision for the nearest level, where theory has a
+    /// closed form for it.
+    fn theoretical_ber(&self, _eb_n0: f64) -> Option<f64> {
+        None
+    }
+
+    /// Extra controls for codes with parameters, shown below the encoding combo
+    /// box. Returns whether any of them changed.
+    fn draw_options(&mut self, _ui: &mut egui::Ui) -> bool {
+        false
+    }
+
+    /// Levels for the bits of `data`, with `scrambling` substituted into the runs
+    /// of zeros of the bipolar codes.
+    fn encode(&self, data: &str, scrambling: Scrambling) -> Vec<i8>;
 }
 
 #[derive(Debug)]
@@ -32,11 +107,48 @@ pub struct Manchester;
 #[derive(Debug)]
 pub struct ManchesterDifferential;
 #[derive(Debug)]
+pub struct Miller;
+#[derive(Debug)]
+pub struct BiphaseMark;
+#[derive(Debug)]
+pub struct BiphaseSpace;
+#[derive(Debug)]
+pub struct CMI;
+#[derive(Debug)]
 pub struct AMI;
+#[derive(Debug)]
+pub struct Pseudoternary;
+#[derive(Debug)]
+pub struct MLT3;
+#[derive(Debug)]
+pub struct TwoB1Q;
+/// M-ary pulse amplitude modulation on the odd levels -(M - 1)..=(M - 1), with
+/// the bit groups optionally Gray mapped so neighbouring levels differ in one bit.
+pub struct PAM {
+    pub order: usize,
+    pub gray: bool,
+}
+/// Duobinary (1 + D) partial-response coding.
+pub struct Duobinary {
+    pub precoder: bool,
+}
+/// Modified duobinary (1 - D^2) partial-response coding.
+pub struct ModifiedDuobinary {
+    pub precoder: bool,
+}
+#[derive(Debug)]
+pub struct UnipolarRZ;
+#[derive(Debug)]
+pub struct PolarRZ;
+#[derive(Debug)]
+pub struct BipolarRZ;
 
 impl Encode for NRZL {
-    fn encode(&self, settings: &DigitalSettings) -> Vec<i8> {
-        let data = &settings.binary_stream;
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        Some(q((2.0 * eb_n0).sqrt()))
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
         let mut result = Vec::with_capacity(data.len());
         data.chars().for_each(|c| {
             result.push(match c {
@@ -50,8 +162,13 @@ impl Encode for NRZL {
 }
 
 impl Encode for NRZI {
-    fn encode(&self, settings: &DigitalSettings) -> Vec<i8> {
-        let data = &settings.binary_stream;
+    /// A bit is wrong when exactly one of the two levels it is read from is.
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        let p = q((2.0 * eb_n0).sqrt());
+        Some(2.0 * p * (1.0 - p))
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
         let mut encoded_data = Vec::with_capacity(data.len());
         let mut toggle = 1;
         data.chars().for_each(|c| {
@@ -65,8 +182,17 @@ impl Encode for NRZI {
 }
 
 impl Encode for Manchester {
-    fn encode(&self, settings: &DigitalSettings) -> Vec<i8> {
-        let data = &settings.binary_stream;
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    /// The decoder only reads the first half of the bit, which carries half its
+    /// energy.
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        Some(q(eb_n0.sqrt()))
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
         let mut encoded_data = Vec::with_capacity(data.len() * 2);
         data.chars().for_each(|c| {
             encoded_data.extend_from_slice(match c {
@@ -80,8 +206,11 @@ impl Encode for Manchester {
 }
 
 impl Encode for ManchesterDifferential {
-    fn encode(&self, settings: &DigitalSettings) -> Vec<i8> {
-        let data = &settings.binary_stream;
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
         let mut encoded_data = Vec::with_capacity(data.len() * 2);
         let seq = [[1, -1], [-1, 1]];
         let mut toggle = 0;
@@ -95,27 +224,391 @@ impl Encode for ManchesterDifferential {
     }
 }
 
+impl Encode for Miller {
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut encoded_data = Vec::with_capacity(data.len() * 2);
+        let mut level = 1;
+        let mut previous = '1';
+        data.chars().for_each(|c| {
+            if c == '0' && previous == '0' {
+                level *= -1;
+            }
+            encoded_data.push(level);
+            if c == '1' {
+                level *= -1;
+            }
+            encoded_data.push(level);
+            previous = c;
+        });
+        encoded_data
+    }
+}
+
+/// Bit error rate of the codes that send one bit value as no pulse and the
+/// other as a pulse of either polarity, with the thresholds half way. No pulse
+/// is lost when the noise crosses either threshold, a pulse only when it ends
+/// up between them, since the wrong polarity still reads as a pulse.
+fn bipolar_ber(eb_n0: f64) -> f64 {
+    let b = eb_n0.sqrt();
+    1.5 * q(b) - 0.5 * q(3.0 * b)
+}
+
+/// Biphase codes always change level at the start of a bit, and also halfway
+/// through it when the bit equals `mid_bit`.
+fn biphase(data: &str, mid_bit: char) -> Vec<i8> {
+    let mut encoded_data = Vec::with_capacity(data.len() * 2);
+    let mut level = -1;
+    data.chars().for_each(|c| {
+        level *= -1;
+        encoded_data.push(level);
+        if c == mid_bit {
+            level *= -1;
+        }
+        encoded_data.push(level);
+    });
+    encoded_data
+}
+
+impl Encode for BiphaseMark {
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        biphase(data, '1')
+    }
+}
+
+impl Encode for BiphaseSpace {
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        biphase(data, '0')
+    }
+}
+
+impl Encode for CMI {
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut encoded_data = Vec::with_capacity(data.len() * 2);
+        let mut toggle = 1;
+        data.chars().for_each(|c| {
+            match c {
+                '0' => encoded_data.extend_from_slice(&[-1, 1]),
+                '1' => {
+                    encoded_data.extend_from_slice(&[toggle, toggle]);
+                    toggle *= -1;
+                }
+                _ => panic!("Invalid input"),
+            };
+        });
+        encoded_data
+    }
+}
+
 impl Encode for AMI {
-    fn encode(&self, settings: &DigitalSettings) -> Vec<i8> {
-        let data = &settings.binary_stream;
-        let encoded_data = match settings.scrambling {
-            Scrambling::None => {
-                let mut toggle = 1;
-                data.chars().fold(Vec::new(), |mut acc, c| {
-                    match c {
-                        '0' => acc.push(0),
-                        '1' => {
-                            acc.push(toggle);
-                            toggle *= -1;
-                        }
-                        _ => {}
-                    };
-                    acc
-                })
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
+
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        Some(bipolar_ber(eb_n0))
+    }
+
+    fn is_bipolar(&self) -> bool {
+        true
+    }
+
+    fn encode(&self, data: &str, scrambling: Scrambling) -> Vec<i8> {
+        self.scramble(data, scrambling)
+    }
+}
+
+impl Encode for Pseudoternary {
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
+
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        Some(bipolar_ber(eb_n0))
+    }
+
+    fn is_bipolar(&self) -> bool {
+        true
+    }
+
+    fn encode(&self, data: &str, scrambling: Scrambling) -> Vec<i8> {
+        self.scramble(data, scrambling)
+    }
+}
+
+impl Encode for MLT3 {
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut encoded_data = Vec::with_capacity(data.len());
+        let cycle = [0, 1, 0, -1];
+        let mut state = 0;
+        data.chars().for_each(|c| {
+            if c == '1' {
+                state = (state + 1) % cycle.len();
             }
-            _ => self.scramble(data, settings.scrambling),
-        };
+            encoded_data.push(cycle[state]);
+        });
+        encoded_data
+    }
+}
+
+impl Encode for TwoB1Q {
+    fn bits_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        PAM { order: 4, gray: true }.theoretical_ber(eb_n0)
+    }
+
+    fn levels(&self) -> Vec<i8> {
+        vec![-3, -1, 1, 3]
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut data = data.to_string();
+        data.push_str(&"0".repeat(self.padding(data.len())));
+        data.as_bytes()
+            .chunks(2)
+            .map(|dibit| match dibit {
+                b"00" => -3,
+                b"01" => -1,
+                b"10" => 3,
+                b"11" => 1,
+                _ => panic!("Invalid input"),
+            })
+            .collect()
+    }
+}
+
+impl Debug for PAM {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PAM-{}", self.order)?;
+        if self.gray {
+            write!(f, " (Gray)")?;
+        }
+        Ok(())
+    }
+}
+
+impl PAM {
+    /// Level for the index of a level counted from the lowest one.
+    pub fn level(&self, index: usize) -> i8 {
+        (2 * index as i8) - (self.order as i8 - 1)
+    }
+
+    /// Index of the level closest to `level`.
+    pub fn index(&self, level: i8) -> usize {
+        ((level as i32 + self.order as i32) / 2).clamp(0, self.order as i32 - 1) as usize
+    }
+}
+
+impl Encode for PAM {
+    fn bits_per_symbol(&self) -> usize {
+        self.order.trailing_zeros() as usize
+    }
+
+    fn levels(&self) -> Vec<i8> {
+        (0..self.order).map(|index| self.level(index)).collect()
+    }
+
+    /// Symbol errors to a neighbouring level, each of which costs one bit with
+    /// Gray mapping.
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        if !self.gray {
+            return None;
+        }
+        let m = self.order as f64;
+        let bits = self.bits_per_symbol() as f64;
+        let symbol_error = 2.0 * (1.0 - 1.0 / m) * q((6.0 * bits / (m * m - 1.0) * eb_n0).sqrt());
+        Some(symbol_error / bits)
+    }
+
+    fn draw_options(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            for order in [2, 4, 8, 16] {
+                changed |= ui.radio_value(&mut self.order, order, format!("M = {}", order)).changed();
+            }
+        });
+        changed | ui.checkbox(&mut self.gray, "Gray mapping").changed()
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut data = data.to_string();
+        data.push_str(&"0".repeat(self.padding(data.len())));
+        data.as_bytes()
+            .chunks(self.bits_per_symbol())
+            .map(|group| {
+                let value = group.iter().fold(0, |acc, &b| (acc << 1) | (b - b'0') as usize);
+                let index = if self.gray {
+                    (1..self.bits_per_symbol()).fold(value, |acc, shift| acc ^ (value >> shift))
+                } else {
+                    value
+                };
+                self.level(index)
+            })
+            .collect()
+    }
+}
+
+impl Debug for Duobinary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Duobinary{}", if self.precoder { " (precoded)" } else { "" })
+    }
+}
+
+impl Debug for ModifiedDuobinary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ModifiedDuobinary{}", if self.precoder { " (precoded)" } else { "" })
+    }
+}
+
+/// Correlative coding of the polar bits a(k) into (a(k) + sign * a(k - delay)) / 2,
+/// which gives the three levels -1, 0 and 1. The precoder sends
+/// b(k) = d(k) xor b(k - delay) instead of the data bits d(k) so that the receiver
+/// can decide every level on its own.
+pub fn partial_response(data: &str, delay: usize, sign: i8, precoder: bool) -> Vec<i8> {
+    let mut sent = vec![-1; delay];
+    data.chars()
+        .map(|c| {
+            let bit = c == '1';
+            let previous = sent[sent.len() - delay];
+            let level = if precoder {
+                if bit {
+                    -previous
+                } else {
+                    previous
+                }
+            } else if bit {
+                1
+            } else {
+                -1
+            };
+            sent.push(level);
+            (level + sign * previous) / 2
+        })
+        .collect()
+}
+
+impl Encode for Duobinary {
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
 
+    fn draw_options(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.checkbox(&mut self.precoder, "Precoder").changed()
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        partial_response(data, 1, 1, self.precoder)
+    }
+}
+
+impl Encode for ModifiedDuobinary {
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
+
+    fn draw_options(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.checkbox(&mut self.precoder, "Precoder").changed()
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        partial_response(data, 2, -1, self.precoder)
+    }
+}
+
+impl Encode for UnipolarRZ {
+    fn levels(&self) -> Vec<i8> {
+        vec![0, 1]
+    }
+
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        Some(q(eb_n0.sqrt()))
+    }
+
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut encoded_data = Vec::with_capacity(data.len() * 2);
+        data.chars().for_each(|c| {
+            encoded_data.extend_from_slice(match c {
+                '1' => &[1, 0],
+                '0' => &[0, 0],
+                _ => panic!("Invalid input"),
+            });
+        });
         encoded_data
     }
 }
+
+impl Encode for PolarRZ {
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
+
+    /// Only a first half decided as the positive level reads as a one, so a one
+    /// is lost halfway to zero and a zero only past it.
+    fn theoretical_ber(&self, eb_n0: f64) -> Option<f64> {
+        let a = (2.0 * eb_n0).sqrt();
+        Some(0.5 * (q(0.5 * a) + q(1.5 * a)))
+    }
+
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &str, _scrambling: Scrambling) -> Vec<i8> {
+        let mut encoded_data = Vec::with_capacity(data.len() * 2);
+        data.chars().for_each(|c| {
+            encoded_data.extend_from_slice(match c {
+                '1' => &[1, 0],
+                '0' => &[-1, 0],
+                _ => panic!("Invalid input"),
+            });
+        });
+        encoded_data
+    }
+}
+
+impl Encode for BipolarRZ {
+    fn levels(&self) -> Vec<i8> {
+        vec![-1, 0, 1]
+    }
+
+    fn elements_per_symbol(&self) -> usize {
+        2
+    }
+
+    fn is_bipolar(&self) -> bool {
+        true
+    }
+
+    fn encode(&self, data: &str, scrambling: Scrambling) -> Vec<i8> {
+        self.scramble(data, scrambling)
+            .into_iter()
+            .flat_map(|x| [x, 0])
+            .collect()
+    }
+}
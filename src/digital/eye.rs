@@ -0,0 +1,217 @@
+use super::channel::Channel;
+
+/// Eye diagram of the line signal, every unit interval of the oversampled
+/// waveform laid over the others. A unit interval is one signal element, which
+/// is half a bit for the codes with half-bit pulses such as Manchester and RZ.
+pub struct Eye {
+    pub enabled: bool,
+    pub filter: bool,
+    /// Cut-off frequency of the receive filter as a multiple of the element rate.
+    pub bandwidth: f32,
+    pub waveform: Vec<f32>,
+    pub samples: usize,
+    pub measurement: Option<Measurement>,
+}
+
+impl Default for Eye {
+    fn default() -> Self {
+        Eye {
+            enabled: false,
+            // Raw samples carry the full channel noise, which closes the eye
+            // long before the matched filter of the decoder makes any errors.
+            filter: true,
+            bandwidth: 0.5,
+            waveform: Vec::new(),
+            samples: 1,
+            measurement: None,
+        }
+    }
+}
+
+/// Opening of the eye, in levels for the height and unit intervals (elements)
+/// otherwise.
+pub struct Measurement {
+    pub height: f32,
+    /// Bottom and top of the narrowest opening at the best sampling phase.
+    pub opening: (f32, f32),
+    /// Spacing of the two levels on either side of the narrowest opening.
+    pub spacing: f32,
+    pub phase: usize,
+    pub width: f32,
+    /// First sampling phase of the open stretch, before `phase` and possibly
+    /// in the previous unit interval.
+    pub open_from: isize,
+    pub jitter: Option<f32>,
+    pub jitter_rms: Option<f32>,
+}
+
+impl Eye {
+    /// Takes the noisy waveform, or holds the clean levels when the channel is
+    /// off, filters it if asked and measures the eye against the sent levels.
+    pub fn update(&mut self, levels: &[i8], waveform: &[f32], channel: &Channel) {
+        let waveform = if waveform.is_empty() {
+            channel.oversample(levels)
+        } else {
+            waveform.to_vec()
+        };
+        self.samples = channel.samples.max(1);
+        self.waveform = if self.filter {
+            low_pass(&waveform, self.samples, self.bandwidth)
+        } else {
+            waveform
+        };
+        self.measurement = measure(&self.waveform, levels, self.samples);
+    }
+}
+
+/// Single-pole RC low-pass filter with its cut-off at `bandwidth` times the
+/// element rate, which smears every element into the next ones.
+pub fn low_pass(waveform: &[f32], samples: usize, bandwidth: f32) -> Vec<f32> {
+    let alpha = 1.0 - (-2.0 * std::f32::consts::PI * bandwidth / samples as f32).exp();
+    let mut y = waveform.first().copied().unwrap_or(0.0);
+    waveform
+        .iter()
+        .map(|&x| {
+            y += alpha * (x - y);
+            y
+        })
+        .collect()
+}
+
+/// Narrowest gap at `phase` between the samples of two neighbouring levels, as
+/// the top of the lower level and the bottom of the upper one, with the
+/// spacing of the levels.
+fn opening(waveform: &[f32], levels: &[i8], samples: usize, phase: usize) -> Option<(f32, f32, f32)> {
+    let mut used: Vec<i8> = levels.to_vec();
+    used.sort_unstable();
+    used.dedup();
+    used.windows(2)
+        .filter_map(|pair| {
+            let at = |level: i8| {
+                levels
+                    .iter()
+                    .enumerate()
+                    .filter(move |&(_, &sent)| sent == level)
+                    .filter_map(|(i, _)| waveform.get(i * samples + phase).copied())
+            };
+            let bottom = at(pair[0]).fold(f32::NEG_INFINITY, f32::max);
+            let top = at(pair[1]).fold(f32::INFINITY, f32::min);
+            Some((bottom, top, (pair[1] - pair[0]) as f32)).filter(|_| bottom.is_finite() && top.is_finite())
+        })
+        .min_by(|a, b| (a.1 - a.0).total_cmp(&(b.1 - b.0)))
+}
+
+/// Threshold crossing times of every transition, in unit intervals from the
+/// element boundary. The crossing nearest the boundary counts when the noise
+/// crosses more than once.
+fn crossings(waveform: &[f32], levels: &[i8], samples: usize) -> Vec<f32> {
+    let mut times = Vec::new();
+    for (i, pair) in levels.windows(2).enumerate() {
+        if pair[0] == pair[1] {
+            continue;
+        }
+        let threshold = (pair[0] as f32 + pair[1] as f32) / 2.0;
+        let boundary = (i + 1) * samples;
+        let start = (boundary - samples / 2).max(1);
+        let end = (boundary + samples).min(waveform.len());
+        let crossing = (start..end)
+            .filter(|&j| (waveform[j - 1] - threshold) * (waveform[j] - threshold) <= 0.0 && waveform[j - 1] != waveform[j])
+            .map(|j| {
+                // Sample j stands for the middle of its slot, at j + 0.5.
+                let fraction = (threshold - waveform[j - 1]) / (waveform[j] - waveform[j - 1]);
+                (j as f32 - 0.5 + fraction - boundary as f32) / samples as f32
+            })
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()));
+        times.extend(crossing);
+    }
+    times
+}
+
+/// Samples the eye at the phase where it opens widest, measures how many
+/// phases around it stay open and how far the transitions wander.
+pub fn measure(waveform: &[f32], levels: &[i8], samples: usize) -> Option<Measurement> {
+    let heights: Vec<Option<(f32, f32, f32)>> = (0..samples).map(|phase| opening(waveform, levels, samples, phase)).collect();
+    let height = |phase: usize| heights[phase].map_or(f32::NEG_INFINITY, |(bottom, top, _)| top - bottom);
+    // Ties go to the phase nearest the middle of the interval.
+    let centre = |phase: usize| (2 * phase + 1).abs_diff(samples);
+    let phase = (0..samples).max_by(|&a, &b| height(a).total_cmp(&height(b)).then(centre(b).cmp(&centre(a))))?;
+    let (bottom, top, spacing) = heights[phase]?;
+
+    let open = |offset: isize| height((phase as isize + offset).rem_euclid(samples as isize) as usize) > 0.0;
+    let (mut before, mut after) = (0, 0);
+    if open(0) {
+        while before + after + 1 < samples && open(-(before as isize) - 1) {
+            before += 1;
+        }
+        while before + after + 1 < samples && open(after as isize + 1) {
+            after += 1;
+        }
+    }
+    let width = if open(0) { (before + after + 1) as f32 / samples as f32 } else { 0.0 };
+    let open_from = if before + after + 1 == samples { 0 } else { phase as isize - before as isize };
+
+    let times = crossings(waveform, levels, samples);
+    let (jitter, jitter_rms) = if times.is_empty() {
+        (None, None)
+    } else {
+        let mean = times.iter().sum::<f32>() / times.len() as f32;
+        let spread = times.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b)) - times.iter().fold(f32::INFINITY, |a, &b| a.min(b));
+        let rms = (times.iter().map(|t| (t - mean).powi(2)).sum::<f32>() / times.len() as f32).sqrt();
+        (Some(spread), Some(rms))
+    };
+
+    Some(Measurement {
+        height: (top - bottom).max(0.0),
+        opening: (bottom, top),
+        spacing,
+        phase,
+        width,
+        open_from,
+        jitter,
+        jitter_rms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(eb_n0: f32) -> Channel {
+        Channel {
+            enabled: true,
+            eb_n0,
+            seed: 25,
+            samples: 16,
+        }
+    }
+
+    fn levels() -> Vec<i8> {
+        (0..200).map(|i: usize| if (i * 7 + i / 3).is_multiple_of(2) { 1 } else { -1 }).collect()
+    }
+
+    #[test]
+    fn clean_eye_is_wide_open() {
+        let levels = levels();
+        let channel = Channel { enabled: false, ..channel(0.0) };
+        let measurement = measure(&channel.oversample(&levels), &levels, channel.samples).unwrap();
+        assert_eq!(measurement.height, 2.0);
+        assert_eq!(measurement.width, 1.0);
+        assert_eq!(measurement.jitter, Some(0.0));
+    }
+
+    #[test]
+    fn filter_opens_a_noisy_eye() {
+        let levels = levels();
+        let channel = channel(12.0);
+        let waveform = channel.transmit(&channel.oversample(&levels), 1.0);
+        let mut eye = Eye { enabled: true, filter: false, ..Eye::default() };
+        eye.update(&levels, &waveform, &channel);
+        let raw = eye.measurement.as_ref().unwrap().height;
+        eye.filter = true;
+        eye.update(&levels, &waveform, &channel);
+        let filtered = eye.measurement.as_ref().unwrap();
+        assert_eq!(raw, 0.0);
+        assert!(filtered.height > 0.5 && filtered.width > 0.3, "{} high, {} UI wide", filtered.height, filtered.width);
+        assert!(Eye::default().filter);
+    }
+}
@@ -0,0 +1,310 @@
+use super::convolutional::Convolutional;
+use super::reed_solomon::ReedSolomon;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelCoding {
+    Hamming74,
+    Hamming1511,
+    SECDED84,
+    SECDED1611,
+    Convolutional(Convolutional),
+    ReedSolomon(ReedSolomon),
+    None,
+}
+
+/// What the channel decoder did to the received bits.
+#[derive(Default)]
+pub struct Correction {
+    pub data: String,
+    /// One line per corrected error.
+    pub corrected: Vec<String>,
+    /// Errors that were detected but could not be corrected.
+    pub errors: Vec<String>,
+    /// States of the surviving path, for the trellis codes.
+    pub trellis: Vec<usize>,
+}
+
+impl ChannelCoding {
+    /// Number of input bits consumed by one codeword.
+    pub fn data_bits(&self) -> usize {
+        match self {
+            ChannelCoding::Hamming74 | ChannelCoding::SECDED84 => 4,
+            ChannelCoding::Hamming1511 | ChannelCoding::SECDED1611 => 11,
+            ChannelCoding::ReedSolomon(_) => 8,
+            ChannelCoding::Convolutional(_) | ChannelCoding::None => 1,
+        }
+    }
+
+    /// Number of coded bits in one full codeword, for the block codes.
+    pub fn codeword_bits(&self) -> Option<usize> {
+        match self {
+            ChannelCoding::Hamming74 => Some(7),
+            ChannelCoding::Hamming1511 => Some(15),
+            ChannelCoding::SECDED84 => Some(8),
+            ChannelCoding::SECDED1611 => Some(16),
+            ChannelCoding::ReedSolomon(code) => Some(8 * code.n),
+            ChannelCoding::Convolutional(_) | ChannelCoding::None => None,
+        }
+    }
+
+    /// Number of zero bits appended to `len` input bits to fill the last codeword.
+    pub fn padding(&self, len: usize) -> usize {
+        (self.data_bits() - len % self.data_bits()) % self.data_bits()
+    }
+}
+
+/// Protects `data` with the selected code. Returns the coded bits together with
+/// the positions of the redundant bits among them.
+pub fn channel_code(data: &str, scheme: ChannelCoding) -> (String, Vec<usize>) {
+    let mut data = data.to_string();
+    data.push_str(&"0".repeat(scheme.padding(data.len())));
+    match scheme {
+        ChannelCoding::Hamming74 => hamming(&data, 3, false),
+        ChannelCoding::Hamming1511 => hamming(&data, 4, false),
+        ChannelCoding::SECDED84 => hamming(&data, 3, true),
+        ChannelCoding::SECDED1611 => hamming(&data, 4, true),
+        ChannelCoding::Convolutional(code) => (code.encode(&data), Vec::new()),
+        ChannelCoding::ReedSolomon(code) => reed_solomon(&data, code),
+        ChannelCoding::None => (data, Vec::new()),
+    }
+}
+
+/// Corrects the received bits. `soft` holds a value from -1 to 1 for each of
+/// them, which the codes that can use soft decisions take instead of the bits.
+pub fn channel_decode(bits: &str, soft: &[f32], scheme: ChannelCoding) -> Correction {
+    match scheme {
+        ChannelCoding::Hamming74 => hamming_decode(bits, 3, false),
+        ChannelCoding::Hamming1511 => hamming_decode(bits, 4, false),
+        ChannelCoding::SECDED84 => hamming_decode(bits, 3, true),
+        ChannelCoding::SECDED1611 => hamming_decode(bits, 4, true),
+        ChannelCoding::Convolutional(code) => code.decode(soft),
+        ChannelCoding::ReedSolomon(code) => reed_solomon_decode(bits, soft, code),
+        ChannelCoding::None => Correction {
+            data: bits.to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Hamming code with `parity` parity bits at the power-of-two positions of a
+/// 2^parity - 1 bit codeword (counting from 1), each covering the positions that
+/// have its bit set. The extended code appends a bit for the parity of the whole
+/// codeword, which tells single from double errors.
+pub fn hamming(data: &str, parity: u32, extended: bool) -> (String, Vec<usize>) {
+    let n = (1 << parity) - 1;
+    let k = n - parity as usize;
+    let length = n + extended as usize;
+    let mut coded = String::with_capacity(data.len() / k * length);
+    let mut parity_bits = Vec::new();
+
+    let data: Vec<bool> = data.chars().map(|c| c == '1').collect();
+    for chunk in data.chunks(k) {
+        let mut codeword = vec![false; n + 1];
+        let mut chunk = chunk.iter();
+        for (position, bit) in codeword.iter_mut().enumerate().skip(1) {
+            if !position.is_power_of_two() {
+                *bit = *chunk.next().unwrap();
+            }
+        }
+        let syndrome = syndrome(&codeword);
+        for i in 0..parity {
+            codeword[1 << i] = (syndrome >> i) & 1 == 1;
+        }
+        if extended {
+            codeword.push(codeword.iter().filter(|&&bit| bit).count() % 2 == 1);
+        }
+
+        let start = coded.len();
+        parity_bits.extend((0..parity).map(|i| start + (1 << i) - 1));
+        if extended {
+            parity_bits.push(start + n);
+        }
+        coded.extend(codeword[1..].iter().map(|&bit| if bit { '1' } else { '0' }));
+    }
+    (coded, parity_bits)
+}
+
+/// XOR of the positions of all set bits of the codeword, which is zero for a
+/// valid codeword and the position of the flipped bit after a single error.
+fn syndrome(codeword: &[bool]) -> usize {
+    (1..codeword.len())
+        .filter(|&position| codeword[position])
+        .fold(0, |acc, position| acc ^ position)
+}
+
+pub fn hamming_decode(bits: &str, parity: u32, extended: bool) -> Correction {
+    let n = (1 << parity) - 1;
+    let length = n + extended as usize;
+    let mut correction = Correction::default();
+
+    let bits: Vec<bool> = bits.chars().map(|c| c == '1').collect();
+    for (i, chunk) in bits.chunks(length).enumerate() {
+        if chunk.len() < length {
+            correction.errors.push(format!("Codeword {} is incomplete", i));
+            break;
+        }
+        let mut codeword = vec![false];
+        codeword.extend_from_slice(&chunk[..n]);
+        let syndrome = syndrome(&codeword);
+        let odd = extended && chunk.iter().filter(|&&bit| bit).count() % 2 == 1;
+
+        if extended && syndrome != 0 && !odd {
+            correction.errors.push(format!("Double error in codeword {}", i));
+        } else if syndrome != 0 {
+            codeword[syndrome] = !codeword[syndrome];
+            correction.corrected.push(format!("Corrected bit {} of codeword {}", syndrome - 1, i));
+        } else if odd {
+            correction.corrected.push(format!("Corrected the overall parity bit of codeword {}", i));
+        }
+
+        correction.data.extend(
+            codeword
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter(|(position, _)| !position.is_power_of_two())
+                .map(|(_, &bit)| if bit { '1' } else { '0' }),
+        );
+    }
+    correction
+}
+
+fn to_bytes(bits: &str) -> Vec<u8> {
+    bits.as_bytes()
+        .chunks_exact(8)
+        .map(|byte| byte.iter().fold(0, |acc, &b| (acc << 1) | (b == b'1') as u8))
+        .collect()
+}
+
+fn to_bits(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:08b}", byte)).collect()
+}
+
+/// Reed-Solomon over the bytes of `data`, most significant bit first.
+pub fn reed_solomon(data: &str, code: ReedSolomon) -> (String, Vec<usize>) {
+    let message = to_bytes(data);
+    let coded = code.encode(&message);
+    let mut parity_bits = Vec::new();
+    let mut start = 0;
+    for chunk in message.chunks(code.k) {
+        let parity = start + 8 * chunk.len();
+        start = parity + 8 * code.parity();
+        parity_bits.extend(parity..start);
+    }
+    (to_bits(&coded), parity_bits)
+}
+
+/// Every byte with a bit of soft value zero, about which the receiver knows
+/// nothing, is handed to the decoder as an erasure.
+pub fn reed_solomon_decode(bits: &str, soft: &[f32], code: ReedSolomon) -> Correction {
+    let erasures: Vec<usize> = soft
+        .chunks(8)
+        .enumerate()
+        .filter(|(_, byte)| byte.contains(&0.0))
+        .map(|(i, _)| i)
+        .collect();
+    let (data, mut correction) = code.decode(&to_bytes(bits), &erasures);
+    correction.data = to_bits(&data);
+    correction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAMMING: [ChannelCoding; 4] = [
+        ChannelCoding::Hamming74,
+        ChannelCoding::Hamming1511,
+        ChannelCoding::SECDED84,
+        ChannelCoding::SECDED1611,
+    ];
+
+    /// Every message of one codeword for the short codes and a spread of them for
+    /// the long ones.
+    fn messages(scheme: ChannelCoding) -> Vec<String> {
+        let k = scheme.data_bits();
+        (0..1usize << k)
+            .step_by(if k > 4 { 37 } else { 1 })
+            .map(|m| format!("{:0k$b}", m, k = k))
+            .collect()
+    }
+
+    fn flip(bits: &str, positions: &[usize]) -> String {
+        bits.chars()
+            .enumerate()
+            .map(|(i, c)| match (positions.contains(&i), c) {
+                (true, '1') => '0',
+                (true, _) => '1',
+                (false, c) => c,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn known_codeword() {
+        // Data 1011 at positions 3, 5, 6 and 7 with p1, p2 and p4 in front.
+        assert_eq!(hamming("1011", 3, false), ("0110011".to_string(), vec![0, 1, 3]));
+        assert_eq!(hamming("1011", 3, true), ("01100110".to_string(), vec![0, 1, 3, 7]));
+    }
+
+    #[test]
+    fn round_trip() {
+        for scheme in HAMMING {
+            let data: String = messages(scheme).concat();
+            let (coded, parity) = channel_code(&data, scheme);
+            let n = scheme.codeword_bits().unwrap();
+            assert_eq!(coded.len(), data.len() / scheme.data_bits() * n);
+            assert_eq!(parity.len(), coded.len() / n * (n - scheme.data_bits()));
+            let correction = channel_decode(&coded, &[], scheme);
+            assert_eq!(correction.data, data, "{:?}", scheme);
+            assert!(correction.corrected.is_empty() && correction.errors.is_empty());
+        }
+    }
+
+    #[test]
+    fn corrects_any_single_error() {
+        for scheme in HAMMING {
+            for message in messages(scheme) {
+                let (coded, _) = channel_code(&message, scheme);
+                for i in 0..coded.len() {
+                    let correction = channel_decode(&flip(&coded, &[i]), &[], scheme);
+                    assert_eq!(correction.data, message, "{:?} bit {}", scheme, i);
+                    assert_eq!(correction.corrected.len(), 1);
+                    assert!(correction.errors.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn secded_detects_double_errors() {
+        for scheme in [ChannelCoding::SECDED84, ChannelCoding::SECDED1611] {
+            for message in messages(scheme) {
+                let (coded, _) = channel_code(&message, scheme);
+                for i in 0..coded.len() {
+                    for j in i + 1..coded.len() {
+                        let correction = channel_decode(&flip(&coded, &[i, j]), &[], scheme);
+                        assert_eq!(correction.errors, ["Double error in codeword 0"], "{:?} bits {} and {}", scheme, i, j);
+                        assert!(correction.corrected.is_empty());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overall_parity_error() {
+        let (coded, parity) = channel_code("1011", ChannelCoding::SECDED84);
+        let correction = channel_decode(&flip(&coded, &[7]), &[], ChannelCoding::SECDED84);
+        assert_eq!(parity.last(), Some(&7));
+        assert_eq!(correction.data, "1011");
+        assert_eq!(correction.corrected, ["Corrected the overall parity bit of codeword 0"]);
+    }
+
+    #[test]
+    fn incomplete_codeword() {
+        let correction = channel_decode("0110011011", &[], ChannelCoding::Hamming74);
+        assert_eq!(correction.data, "1011");
+        assert_eq!(correction.errors, ["Codeword 1 is incomplete"]);
+    }
+}
@@ -0,0 +1,123 @@
+use super::crc::X25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Framing {
+    HDLC,
+    None,
+}
+
+/// The 0x7E flag that opens and closes every HDLC frame.
+pub const FLAG: &str = "01111110";
+
+/// Wraps `data` and its FCS in flags, inserting a zero after every five
+/// consecutive ones in between. Returns the frame together with the positions of
+/// the inserted zeros.
+pub fn hdlc_frame(data: &str) -> (String, Vec<usize>) {
+    let mut frame = String::from(FLAG);
+    let mut stuffed = Vec::new();
+    let mut ones = 0;
+    for c in data.chars().chain(X25.checksum(data).chars()) {
+        frame.push(c);
+        ones = if c == '1' { ones + 1 } else { 0 };
+        if ones == 5 {
+            stuffed.push(frame.len());
+            frame.push('0');
+            ones = 0;
+        }
+    }
+    frame.push_str(FLAG);
+    (frame, stuffed)
+}
+
+/// Finds the frame between the first two flags, removes the inserted zeros and
+/// checks the FCS.
+pub fn hdlc_deframe(bits: &str) -> (String, Vec<String>) {
+    let mut errors = Vec::new();
+    let start = match bits.find(FLAG) {
+        Some(start) => start + FLAG.len(),
+        None => return (String::new(), vec!["No opening flag".to_string()]),
+    };
+    let body = match bits[start..].find(FLAG) {
+        Some(end) => &bits[start..start + end],
+        None => {
+            errors.push("No closing flag".to_string());
+            &bits[start..]
+        }
+    };
+
+    let mut destuffed = String::with_capacity(body.len());
+    let mut ones = 0;
+    for c in body.chars() {
+        if ones == 5 {
+            ones = 0;
+            if c == '0' {
+                continue;
+            }
+            errors.push("Six consecutive ones inside the frame".to_string());
+        }
+        ones = if c == '1' { ones + 1 } else { 0 };
+        destuffed.push(c);
+    }
+
+    if destuffed.len() < 16 {
+        errors.push("Frame too short for the FCS".to_string());
+        return (destuffed, errors);
+    }
+    let (data, fcs) = destuffed.split_at(destuffed.len() - 16);
+    if X25.checksum(data) != fcs {
+        errors.push("FCS mismatch".to_string());
+    }
+    (data.to_string(), errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    /// The bit-serial FCS of RFC 1662, shifting the bits in as they are sent.
+    fn fcs16(bits: &str) -> String {
+        let crc = bits.chars().fold(0xffffu16, |crc, c| {
+            let feedback = (crc ^ (c == '1') as u16) & 1;
+            (crc >> 1) ^ if feedback == 1 { 0x8408 } else { 0 }
+        });
+        (0..16).map(|i| if (!crc >> i) & 1 == 1 { '1' } else { '0' }).collect()
+    }
+
+    fn random_bits(rng: &mut StdRng, len: usize) -> String {
+        (0..len).map(|_| if rng.gen() { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn fcs_matches_the_bit_serial_register() {
+        let mut rng = StdRng::seed_from_u64(17);
+        for len in 0..100 {
+            let bits = random_bits(&mut rng, len);
+            assert_eq!(X25.checksum(&bits), fcs16(&bits), "{}", bits);
+        }
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut rng = StdRng::seed_from_u64(18);
+        for len in [0, 1, 5, 6, 40, 200] {
+            let data = random_bits(&mut rng, len);
+            let (frame, stuffed) = hdlc_frame(&data);
+            assert!(stuffed.iter().all(|&i| &frame[i..i + 1] == "0"));
+            assert_eq!(hdlc_deframe(&frame), (data, Vec::new()));
+        }
+        let (frame, stuffed) = hdlc_frame("11111111");
+        assert_eq!(stuffed[0], FLAG.len() + 5);
+        assert!(!frame[FLAG.len()..frame.len() - FLAG.len()].contains("111111"));
+    }
+
+    #[test]
+    fn corrupted_frame_fails_the_fcs() {
+        let (frame, _) = hdlc_frame("1010011010");
+        let mut bits: Vec<char> = frame.chars().collect();
+        bits[FLAG.len() + 2] = if bits[FLAG.len() + 2] == '1' { '0' } else { '1' };
+        let (_, errors) = hdlc_deframe(&bits.into_iter().collect::<String>());
+        assert_eq!(errors, ["FCS mismatch"]);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interleaving {
+    /// Written into the rows of a matrix and read out of its columns.
+    Block { rows: usize, columns: usize },
+    /// Forney's interleaver: symbols go round `branches` delay lines, the i-th of
+    /// which holds i * `depth` symbols.
+    Convolutional { branches: usize, depth: usize },
+    None,
+}
+
+impl Interleaving {
+    /// Symbols the interleaver and deinterleaver hold back between them, which
+    /// have to be flushed through with padding.
+    pub fn delay(&self) -> usize {
+        match *self {
+            Interleaving::Convolutional { branches, depth } => branches * (branches - 1) * depth,
+            _ => 0,
+        }
+    }
+}
+
+/// Reorders `symbols`, padding them with `fill` to whole matrices or to flush the
+/// delay lines.
+pub fn interleave<T: Copy>(symbols: &[T], scheme: Interleaving, fill: T) -> Vec<T> {
+    match scheme {
+        Interleaving::Block { rows, columns } => {
+            let size = rows * columns;
+            let mut padded = symbols.to_vec();
+            padded.resize(symbols.len().div_ceil(size) * size, fill);
+            padded
+                .chunks(size)
+                .flat_map(|matrix| (0..size).map(move |i| matrix[(i % rows) * columns + i / rows]))
+                .collect()
+        }
+        Interleaving::Convolutional { branches, depth } => {
+            let input = symbols.iter().copied().chain(std::iter::repeat_n(fill, scheme.delay()));
+            delay_lines(input, branches, |branch| branch * depth, fill)
+        }
+        Interleaving::None => symbols.to_vec(),
+    }
+}
+
+/// Undoes `interleave`, dropping the symbols that only flushed the delay lines
+/// but keeping any padding of the last matrix.
+pub fn deinterleave<T: Copy + Default>(symbols: &[T], scheme: Interleaving) -> Vec<T> {
+    match scheme {
+        Interleaving::Block { rows, columns } => {
+            let size = rows * columns;
+            let mut padded = symbols.to_vec();
+            padded.resize(symbols.len().div_ceil(size) * size, T::default());
+            padded
+                .chunks(size)
+                .flat_map(|matrix| (0..size).map(move |i| matrix[(i % columns) * rows + i / columns]))
+                .collect()
+        }
+        Interleaving::Convolutional { branches, depth } => {
+            let input = symbols.iter().copied();
+            let output = delay_lines(input, branches, |branch| (branches - 1 - branch) * depth, T::default());
+            output.into_iter().skip(scheme.delay()).collect()
+        }
+        Interleaving::None => symbols.to_vec(),
+    }
+}
+
+fn delay_lines<T: Copy>(
+    input: impl Iterator<Item = T>,
+    branches: usize,
+    length: impl Fn(usize) -> usize,
+    fill: T,
+) -> Vec<T> {
+    let mut lines: Vec<VecDeque<T>> = (0..branches)
+        .map(|branch| std::iter::repeat_n(fill, length(branch)).collect())
+        .collect();
+    input
+        .enumerate()
+        .map(|(i, symbol)| {
+            let line = &mut lines[i % branches];
+            line.push_back(symbol);
+            line.pop_front().unwrap()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    #[test]
+    fn block_reads_columns() {
+        let symbols: Vec<char> = "abcdefghij".chars().collect();
+        let interleaved: String = interleave(&symbols, Interleaving::Block { rows: 3, columns: 4 }, '-').into_iter().collect();
+        assert_eq!(interleaved, "aeibfjcg-dh-");
+    }
+
+    #[test]
+    fn convolutional_delay() {
+        assert_eq!(Interleaving::Convolutional { branches: 4, depth: 2 }.delay(), 24);
+        assert_eq!(Interleaving::Block { rows: 3, columns: 4 }.delay(), 0);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut rng = StdRng::seed_from_u64(22);
+        let schemes = [
+            Interleaving::None,
+            Interleaving::Block { rows: 3, columns: 4 },
+            Interleaving::Block { rows: 8, columns: 1 },
+            Interleaving::Convolutional { branches: 4, depth: 2 },
+            Interleaving::Convolutional { branches: 1, depth: 3 },
+        ];
+        for scheme in schemes {
+            for _ in 0..20 {
+                let len: usize = rng.gen_range(0..100);
+                let symbols: Vec<u8> = (0..len).map(|_| rng.gen_range(1..=255)).collect();
+                let interleaved = interleave(&symbols, scheme, 0);
+                let expected = match scheme {
+                    Interleaving::Block { rows, columns } => len.div_ceil(rows * columns) * rows * columns,
+                    _ => len + scheme.delay(),
+                };
+                assert_eq!(interleaved.len(), expected, "{:?}", scheme);
+                assert_eq!(interleaved.iter().filter(|&&x| x == 0).count(), expected - len, "{:?}", scheme);
+
+                let deinterleaved = deinterleave(&interleaved, scheme);
+                assert_eq!(deinterleaved[..len], symbols[..], "{:?}", scheme);
+                // The padding of the last matrix comes back too, the flush does not.
+                assert!(deinterleaved[len..].iter().all(|&x| x == 0), "{:?}", scheme);
+                assert_eq!(deinterleaved.len(), expected - scheme.delay(), "{:?}", scheme);
+            }
+        }
+    }
+
+    #[test]
+    fn bursts_are_spread() {
+        let symbols: Vec<usize> = (0..64).collect();
+        let scheme = Interleaving::Convolutional { branches: 4, depth: 2 };
+        let interleaved = interleave(&symbols, scheme, usize::MAX);
+        // Four neighbours on the line come from inputs at least a branch cycle apart.
+        for burst in interleaved.windows(4).filter(|burst| burst.iter().all(|&x| x != usize::MAX)) {
+            let mut sorted = burst.to_vec();
+            sorted.sort_unstable();
+            assert!(sorted.windows(2).all(|pair| pair[1] - pair[0] >= 4), "{:?}", burst);
+        }
+    }
+}
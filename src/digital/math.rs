@@ -0,0 +1,47 @@
+/// Complementary error function with a fractional error below 1.2e-7, after
+/// Numerical Recipes.
+pub fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, c| acc * t + c);
+    let r = t * (-z * z + polynomial).exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Tail probability of the standard normal distribution.
+pub fn q(x: f64) -> f64 {
+    0.5 * erfc(x / std::f64::consts::SQRT_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables() {
+        for (x, expected) in [(0.0, 1.0), (0.5, 0.4795001221869535), (1.0, 0.15729920705028513), (-1.0, 1.8427007929497148)] {
+            assert!((erfc(x) - expected).abs() < 1e-7, "erfc({})", x);
+        }
+        assert!((q(0.0) - 0.5).abs() < 1e-7);
+        assert!((q(1.96) - 0.024997895148220435).abs() < 1e-7);
+        assert!((q(4.753424) / 1e-6 - 1.0).abs() < 1e-3);
+    }
+}
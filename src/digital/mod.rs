@@ -1,30 +1,674 @@
+pub mod ber;
+pub mod block;
+pub mod channel;
+pub mod convolutional;
+pub mod crc;
+pub mod decoding;
 pub mod encoding;
+pub mod eye;
+pub mod fec;
+pub mod framing;
+pub mod interleave;
+pub mod math;
+pub mod reed_solomon;
 pub mod scramble;
 
+use self::ber::{Ber, BerCurve, Sweep};
+use self::block::{block_code, block_decode, Block, BlockCoding};
+use self::channel::{integrate, slice, Channel};
+use self::convolutional::Convolutional;
+use self::crc::{Crc, CrcKind};
+use self::eye::Eye;
+use self::fec::{channel_code, channel_decode, ChannelCoding};
+use self::framing::{hdlc_deframe, hdlc_frame, Framing, FLAG};
+use self::interleave::{deinterleave, interleave, Interleaving};
+use self::reed_solomon::ReedSolomon;
 use super::*;
 use nannou_egui::egui;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// A run of bits on the line that one of the coding stages points out.
+pub struct Highlight {
+    pub bits: Range<usize>,
+    pub color: Rgba,
+}
 
 pub struct DigitalSettings {
     pub binary_stream: String,
     pub longest_palindrome: String,
+    pub checked_stream: String,
+    pub checksum: String,
+    pub framed_stream: String,
+    pub protected_stream: String,
+    pub interleaved_stream: String,
+    pub unscrambled_stream: String,
+    pub coded_stream: String,
+    pub blocks: Vec<Block>,
+    pub highlights: Vec<Highlight>,
     pub result: Vec<i8>,
+    pub waveform: Vec<f32>,
+    pub received: Vec<i8>,
+    pub decoded_stream: String,
+    pub decoded_message: String,
+    pub decode_errors: Vec<String>,
+    pub corrections: Vec<String>,
+    pub trellis: Vec<usize>,
+    pub bit_flips: String,
+    pub flips: Vec<usize>,
+    pub bit_erasures: String,
+    pub erasures: Vec<usize>,
+    pub channel_errors: Vec<usize>,
+    pub violations: Vec<(usize, Violation)>,
     pub encoding: Box<dyn Encode>,
     pub scrambling: Scrambling,
+    pub crc: CrcKind,
+    pub crc_polynomial: String,
+    pub custom_crc: Crc,
+    pub crc_append: bool,
+    pub framing: Framing,
+    pub channel_coding: ChannelCoding,
+    pub generators: String,
+    pub interleaving: Interleaving,
+    pub block_coding: BlockCoding,
+    pub control_symbols: bool,
+    pub lfsr_enabled: bool,
+    pub lfsr_polynomial: String,
+    pub lfsr: Lfsr,
+    pub channel: Channel,
+    pub ber: Ber,
+    pub eye: Eye,
+}
+
+/// Names of every line code, as offered in the encoding combo box.
+pub const ENCODINGS: [&str; 18] = [
+    "NRZ-L",
+    "NRZ-I",
+    "Manchester",
+    "Differential Manchester",
+    "Miller",
+    "Biphase-Mark",
+    "Biphase-Space",
+    "CMI",
+    "AMI",
+    "Pseudoternary",
+    "MLT-3",
+    "2B1Q",
+    "PAM",
+    "Duobinary",
+    "Modified Duobinary",
+    "Unipolar RZ",
+    "Polar RZ",
+    "Bipolar RZ",
+];
+
+pub fn encoding(name: &str) -> Option<Box<dyn Encode>> {
+    Some(match name {
+        "NRZ-L" => Box::new(NRZL),
+        "NRZ-I" => Box::new(NRZI),
+        "Manchester" => Box::new(Manchester),
+        "Differential Manchester" => Box::new(ManchesterDifferential),
+        "Miller" => Box::new(Miller),
+        "Biphase-Mark" => Box::new(BiphaseMark),
+        "Biphase-Space" => Box::new(BiphaseSpace),
+        "CMI" => Box::new(CMI),
+        "AMI" => Box::new(AMI),
+        "Pseudoternary" => Box::new(Pseudoternary),
+        "MLT-3" => Box::new(MLT3),
+        "2B1Q" => Box::new(TwoB1Q),
+        "PAM" => Box::new(PAM { order: 4, gray: true }),
+        "Duobinary" => Box::new(Duobinary { precoder: true }),
+        "Modified Duobinary" => Box::new(ModifiedDuobinary { precoder: true }),
+        "Unipolar RZ" => Box::new(UnipolarRZ),
+        "Polar RZ" => Box::new(PolarRZ),
+        "Bipolar RZ" => Box::new(BipolarRZ),
+        _ => return None,
+    })
+}
+
+/// Runs the message through every transmit stage in turn:
+///
+/// - CRC
+/// - framer
+/// - channel coder
+/// - interleaver
+/// - block coder
+/// - LFSR scrambler
+/// - line code
+/// - AWGN channel
+/// - eye diagram, when it is shown
+///
+/// Highlights only survive stages that keep every bit in place.
+pub fn encode(settings: &mut DigitalSettings) {
+    settings.highlights.clear();
+    let crc = settings.crc.crc(settings.custom_crc);
+    let checksum = crc.map(|crc| crc.checksum(&settings.binary_stream)).unwrap_or_default();
+    settings.checked_stream = settings.binary_stream.clone();
+    if settings.crc_append {
+        let start = settings.checked_stream.len();
+        settings.checked_stream.push_str(&checksum);
+        settings.highlights.push(Highlight {
+            bits: start..settings.checked_stream.len(),
+            color: rgba(0.0, 0.8, 0.8, 0.3),
+        });
+    }
+    settings.checksum = checksum;
+
+    settings.framed_stream = match settings.framing {
+        Framing::HDLC => {
+            let (frame, stuffed) = hdlc_frame(&settings.checked_stream);
+            settings.highlights.clear();
+            let flag = rgba(0.6, 0.2, 0.8, 0.35);
+            let closing = frame.len() - FLAG.len();
+            settings.highlights.push(Highlight { bits: 0..FLAG.len(), color: flag });
+            settings.highlights.push(Highlight { bits: closing..frame.len(), color: flag });
+            settings.highlights.extend(stuffed.into_iter().map(|i| Highlight {
+                bits: i..i + 1,
+                color: rgba(1.0, 0.0, 0.0, 0.4),
+            }));
+            frame
+        }
+        Framing::None => settings.checked_stream.clone(),
+    };
+
+    let (protected, parity) = channel_code(&settings.framed_stream, settings.channel_coding);
+    if settings.channel_coding != ChannelCoding::None {
+        settings.highlights.clear();
+        settings.highlights.extend(parity.into_iter().map(|i| Highlight {
+            bits: i..i + 1,
+            color: rgba(1.0, 0.85, 0.0, 0.35),
+        }));
+    }
+    settings.protected_stream = protected;
+
+    let bits: Vec<char> = settings.protected_stream.chars().collect();
+    settings.interleaved_stream = interleave(&bits, settings.interleaving, '0').into_iter().collect();
+    if settings.interleaving != Interleaving::None {
+        let sources: Vec<Option<usize>> = (0..bits.len()).map(Some).collect();
+        let sources = interleave(&sources, settings.interleaving, None);
+        let highlights = std::mem::take(&mut settings.highlights);
+        for (i, source) in sources.into_iter().enumerate() {
+            let source = match source {
+                Some(source) => source,
+                None => continue,
+            };
+            if let Some(highlight) = highlights.iter().find(|highlight| highlight.bits.contains(&source)) {
+                settings.highlights.push(Highlight { bits: i..i + 1, color: highlight.color });
+            }
+        }
+    }
+
+    settings.blocks = block_code(
+        &settings.interleaved_stream,
+        settings.block_coding,
+        settings.control_symbols,
+    );
+    settings.unscrambled_stream = if settings.block_coding == BlockCoding::None {
+        settings.interleaved_stream.clone()
+    } else {
+        settings.highlights.clear();
+        settings.blocks.iter().map(|block| block.bits.as_str()).collect()
+    };
+    settings.coded_stream = if settings.lfsr_enabled {
+        let mut state = settings.lfsr.seed;
+        settings.lfsr.scramble(&settings.unscrambled_stream, &mut state)
+    } else {
+        settings.unscrambled_stream.clone()
+    };
+    settings.result = settings.encoding.encode(&settings.coded_stream, settings.scrambling);
+    settings.waveform = if settings.channel.enabled {
+        let clean = settings.channel.oversample(&settings.result);
+        let encoding = &settings.encoding;
+        let bits_per_element = encoding.bits_per_symbol() as f32 / encoding.elements_per_symbol() as f32;
+        settings.channel.transmit(&clean, bits_per_element)
+    } else {
+        Vec::new()
+    };
+    if settings.eye.enabled {
+        settings.eye.update(&settings.result, &settings.waveform, &settings.channel);
+    }
+}
+
+/// Undoes the transmit stages in reverse:
+///
+/// - decides the levels of the noisy waveform
+/// - reads the bits back with the line code
+/// - flips and erases the requested bits
+/// - LFSR descrambler
+/// - block decoder
+/// - deinterleaver
+/// - channel decoder
+/// - deframer
+/// - CRC check
+///
+/// Each stage's padding is dropped on the way.
+pub fn decode(settings: &mut DigitalSettings) {
+    let (received, filtered) = if settings.channel.enabled {
+        let filtered = integrate(&settings.waveform, settings.channel.samples);
+        (slice(&filtered, &settings.encoding.levels()), filtered)
+    } else {
+        (settings.result.clone(), Vec::new())
+    };
+    let mut decoded = settings.encoding.decode(&received, settings.scrambling);
+    decoded.truncate(settings.coded_stream.len());
+    let decoded: String = decoded
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match (settings.flips.contains(&i), c) {
+            (true, '1') => '0',
+            (true, _) => '1',
+            (false, c) => c,
+        })
+        .collect();
+    let channel_soft = if settings.channel.enabled {
+        settings.encoding.soft_decode(&filtered)
+    } else {
+        None
+    };
+    let soft: Vec<f32> = decoded
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let reliability = match &channel_soft {
+                _ if settings.erasures.contains(&i) => 0.0,
+                Some(values) => values.get(i).map_or(1.0, |value| value.abs()),
+                None => 1.0,
+            };
+            if c == '1' {
+                reliability
+            } else {
+                -reliability
+            }
+        })
+        .collect();
+    let descrambled = if settings.lfsr_enabled {
+        let mut state = settings.lfsr.seed;
+        settings.lfsr.descramble(&decoded, &mut state)
+    } else {
+        decoded.clone()
+    };
+    let (mut message, mut errors) = block_decode(&descrambled, settings.block_coding);
+    message.truncate(settings.interleaved_stream.len());
+    // Soft values keep their reliability through the LFSR but not through the
+    // block decoder.
+    let soft: Vec<f32> = message
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let reliability = if settings.block_coding == BlockCoding::None {
+                soft[i].abs()
+            } else {
+                1.0
+            };
+            if c == '1' {
+                reliability
+            } else {
+                -reliability
+            }
+        })
+        .collect();
+
+    settings.channel_errors = message
+        .chars()
+        .zip(settings.interleaved_stream.chars())
+        .enumerate()
+        .filter(|(_, (received, sent))| received != sent)
+        .map(|(i, _)| i)
+        .collect();
+    let bits: Vec<char> = message.chars().collect();
+    let mut message: String = deinterleave(&bits, settings.interleaving).into_iter().collect();
+    message.truncate(settings.protected_stream.len());
+    let mut soft = deinterleave(&soft, settings.interleaving);
+    soft.truncate(settings.protected_stream.len());
+
+    let correction = channel_decode(&message, &soft, settings.channel_coding);
+    let mut message = correction.data;
+    message.truncate(settings.framed_stream.len());
+    errors.extend(correction.errors);
+    if settings.framing == Framing::HDLC {
+        let (data, frame_errors) = hdlc_deframe(&message);
+        message = data;
+        errors.extend(frame_errors);
+    }
+    match settings.crc.crc(settings.custom_crc) {
+        Some(crc) if settings.crc_append => {
+            if message.len() < crc.width {
+                errors.push("Message too short for the CRC".to_string());
+            } else {
+                let checksum = message.split_off(message.len() - crc.width);
+                if crc.checksum(&message) != checksum {
+                    errors.push("CRC mismatch".to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+    settings.decoded_stream = decoded;
+    settings.decoded_message = message;
+    settings.decode_errors = errors;
+    settings.corrections = correction.corrected;
+    settings.trellis = correction.trellis;
+
+    settings.violations = if settings.encoding.is_bipolar() {
+        let pulses: Vec<i8> = received
+            .iter()
+            .step_by(settings.encoding.elements_per_symbol())
+            .copied()
+            .collect();
+        violations(&pulses, settings.scrambling)
+    } else {
+        Vec::new()
+    };
+    settings.received = received;
 }
 
+/// Gives a running BER sweep a slice of the frame, whatever is on screen, and
+/// adds its curve once done. The sweep stops when the line code it runs for is
+/// swapped out.
+pub fn update(settings: &mut DigitalSettings) {
+    let ber = &mut settings.ber;
+    let Some(sweep) = &mut ber.running else {
+        return;
+    };
+    if sweep.name != format!("{:?}", settings.encoding) {
+        ber.running = None;
+        return;
+    }
+    let start = Instant::now();
+    while !sweep.done() && start.elapsed() < Duration::from_millis(30) {
+        sweep.advance(settings.encoding.as_ref());
+    }
+    if sweep.done() {
+        let Sweep { name, points, .. } = ber.running.take().unwrap();
+        ber.curves.retain(|curve| curve.name != name);
+        ber.curves.push(BerCurve { name, points });
+        ber.show_plot = true;
+    }
+}
+
+/// Whether the channel decoder gets reliabilities rather than bare decisions:
+/// from erasures or from the matched filter of a line code with a soft output,
+/// and only as long as no block decoder sits in between.
+fn soft_values(settings: &DigitalSettings) -> bool {
+    let channel = settings.channel.enabled && settings.encoding.has_soft_output();
+    settings.block_coding == BlockCoding::None && (channel || !settings.erasures.is_empty())
+}
+
+/// Draws the controls of every stage. The pipeline only runs again at the end of
+/// a frame in which one of them changed.
 pub fn draw_ui(ui: &mut egui::Ui, settings: &mut DigitalSettings) {
+    let mut changed = false;
     ui.vertical(|ui| {
         ui.label("Binary Message:");
         ui.add_space(5.0);
-        if ui.text_edit_singleline(&mut settings.binary_stream).has_focus() {
-            if crate::utils::validate_input(&settings.binary_stream) {
-                settings.result = settings.encoding.encode(&settings);
-                if settings.binary_stream.len() > 1 {
-                    settings.longest_palindrome = crate::utils::longest_palindrome(settings.binary_stream.clone());
+        let response = ui.text_edit_singleline(&mut settings.binary_stream);
+        if crate::utils::validate_input(&settings.binary_stream) {
+            if response.changed() && settings.binary_stream.len() > 1 {
+                settings.longest_palindrome = crate::utils::longest_palindrome(settings.binary_stream.clone());
+            }
+        } else if response.has_focus() {
+            ui.label("Invalid input");
+        }
+        changed |= response.changed();
+    });
+
+    ui.vertical(|ui| {
+        ui.label("CRC:");
+        ui.add_space(5.0);
+        egui::ComboBox::from_id_source(5)
+            .selected_text(format!("{:?}", settings.crc))
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut settings.crc, CrcKind::None, "None").changed();
+                changed |= ui.selectable_value(&mut settings.crc, CrcKind::CRC8, "CRC-8").changed();
+                changed |= ui.selectable_value(&mut settings.crc, CrcKind::CRC16CCITTFalse, "CRC-16/CCITT-FALSE").changed();
+                changed |= ui.selectable_value(&mut settings.crc, CrcKind::CRC32, "CRC-32").changed();
+                changed |= ui.selectable_value(&mut settings.crc, CrcKind::Custom, "Custom").changed();
+            });
+
+        if settings.crc == CrcKind::Custom {
+            ui.horizontal(|ui| {
+                ui.label("Generator:");
+                changed |= ui.text_edit_singleline(&mut settings.crc_polynomial).changed();
+            });
+            match Crc::parse(&settings.crc_polynomial) {
+                Some(crc) => settings.custom_crc = crc,
+                None => {
+                    ui.label("Invalid polynomial, expected e.g. x^3 + x + 1");
+                }
+            }
+        }
+        if settings.crc != CrcKind::None {
+            changed |= ui.checkbox(&mut settings.crc_append, "Append checksum to the message").changed();
+            ui.label("Checksum:");
+            ui.monospace(&settings.checksum);
+            // Written out only while the panel is open, and only as far as shown.
+            let crc = settings.crc.crc(settings.custom_crc);
+            ui.collapsing("Long Division", |ui| {
+                const MAX_LINES: usize = 64;
+                let (lines, total) = crc.map(|crc| crc.divide(&settings.binary_stream, MAX_LINES)).unwrap_or_default();
+                for line in &lines {
+                    ui.monospace(line);
+                }
+                if total > lines.len() {
+                    ui.label(format!("... {} more line(s)", total - lines.len()));
+                }
+            });
+        }
+    });
+
+    ui.vertical(|ui| {
+        ui.label("Framing:");
+        ui.add_space(5.0);
+        egui::ComboBox::from_id_source(4)
+            .selected_text(format!("{:?}", settings.framing))
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut settings.framing, Framing::None, "None").changed();
+                changed |= ui.selectable_value(&mut settings.framing, Framing::HDLC, "HDLC").changed();
+            });
+
+        if settings.framing != Framing::None {
+            let overhead = 2 * FLAG.len() + 16 + settings.checked_stream.len();
+            let stuffed = settings.framed_stream.len().saturating_sub(overhead);
+            ui.label(format!("Frame ({} stuffed bit(s)):", stuffed));
+            ui.monospace(&settings.framed_stream);
+        }
+    });
+
+    ui.vertical(|ui| {
+        ui.label("Error Correction:");
+        ui.add_space(5.0);
+        egui::ComboBox::from_id_source(6)
+            .selected_text(format!("{:?}", settings.channel_coding))
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut settings.channel_coding, ChannelCoding::None, "None").changed();
+                changed |= ui.selectable_value(&mut settings.channel_coding, ChannelCoding::Hamming74, "Hamming(7,4)").changed();
+                changed |= ui.selectable_value(&mut settings.channel_coding, ChannelCoding::Hamming1511, "Hamming(15,11)").changed();
+                changed |= ui.selectable_value(&mut settings.channel_coding, ChannelCoding::SECDED84, "SECDED(8,4)").changed();
+                changed |= ui.selectable_value(&mut settings.channel_coding, ChannelCoding::SECDED1611, "SECDED(16,11)").changed();
+                let convolutional = matches!(settings.channel_coding, ChannelCoding::Convolutional(_));
+                if ui.selectable_label(convolutional, "Convolutional").clicked() && !convolutional {
+                    settings.channel_coding = ChannelCoding::Convolutional(Convolutional::default());
+                    settings.generators = "7, 5".to_string();
+                    changed = true;
+                }
+                let reed_solomon = matches!(settings.channel_coding, ChannelCoding::ReedSolomon(_));
+                if ui.selectable_label(reed_solomon, "Reed-Solomon").clicked() && !reed_solomon {
+                    settings.channel_coding = ChannelCoding::ReedSolomon(ReedSolomon::default());
+                    changed = true;
+                }
+            });
+
+        if let ChannelCoding::ReedSolomon(code) = &mut settings.channel_coding {
+            ui.horizontal(|ui| {
+                for (n, k) in [(255, 223), (204, 188)] {
+                    if ui.button(format!("RS({},{})", n, k)).clicked() {
+                        *code = ReedSolomon { n, k };
+                        changed = true;
+                    }
+                }
+                ui.label("n:");
+                changed |= ui.add(egui::DragValue::new(&mut code.n).clamp_range(2..=255)).changed();
+                ui.label("k:");
+                changed |= ui.add(egui::DragValue::new(&mut code.k).clamp_range(1..=254)).changed();
+            });
+            code.k = code.k.min(code.n - 1);
+            ui.label(format!(
+                "Corrects up to {} symbol error(s) or {} erasure(s) per codeword",
+                code.parity() / 2,
+                code.parity()
+            ));
+        }
+
+        let soft = soft_values(settings);
+        if let ChannelCoding::Convolutional(code) = &mut settings.channel_coding {
+            ui.horizontal(|ui| {
+                ui.label("Constraint length:");
+                let constraint = ui.add(egui::DragValue::new(&mut code.constraint).clamp_range(2..=9)).changed();
+                ui.label("Generators (octal):");
+                changed |= ui.text_edit_singleline(&mut settings.generators).changed();
+                // Generators that no longer fit the new constraint length make way
+                // for the usual ones of that length.
+                if constraint && Convolutional::parse_generators(&settings.generators, code.constraint).is_none() {
+                    let [a, b] = Convolutional::standard_generators(code.constraint);
+                    settings.generators = format!("{:o}, {:o}", a, b);
+                }
+                changed |= constraint;
+            });
+            match Convolutional::parse_generators(&settings.generators, code.constraint) {
+                Some(generators) => code.generators = generators,
+                None => {
+                    ui.label("Invalid generators, expected two octal numbers below 2^K, e.g. 7, 5");
+                }
+            }
+            if !soft && code.soft {
+                code.soft = false;
+                changed = true;
+            }
+            ui.horizontal(|ui| {
+                changed |= ui.radio_value(&mut code.soft, false, "Hard decisions").changed();
+                ui.set_enabled(soft);
+                changed |= ui.radio_value(&mut code.soft, true, "Soft decisions").changed();
+            });
+            if !soft {
+                let codes: Vec<&str> = ENCODINGS
+                    .into_iter()
+                    .filter(|name| encoding(name).is_some_and(|code| code.has_soft_output()))
+                    .collect();
+                ui.label(format!(
+                    "Soft decisions need erasures or the AWGN channel with one of {}, and no block coding",
+                    codes.join(", ")
+                ));
+            }
+            let code = *code;
+            ui.collapsing("Trellis", |ui| draw_trellis(ui, &code, &settings.trellis));
+        }
+
+        if settings.channel_coding != ChannelCoding::None {
+            let padding = settings.channel_coding.padding(settings.framed_stream.len());
+            if padding > 0 {
+                ui.label(format!("Padded with {} trailing 0 bit(s) to fill the last codeword", padding));
+            }
+            ui.label("Codewords:");
+            ui.monospace(&settings.protected_stream);
+        }
+    });
+
+    ui.vertical(|ui| {
+        ui.label("Interleaving:");
+        ui.add_space(5.0);
+        egui::ComboBox::from_id_source(7)
+            .selected_text(format!("{:?}", settings.interleaving))
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut settings.interleaving, Interleaving::None, "None").changed();
+                let block = matches!(settings.interleaving, Interleaving::Block { .. });
+                if ui.selectable_label(block, "Block").clicked() && !block {
+                    settings.interleaving = Interleaving::Block { rows: 4, columns: 7 };
+                    changed = true;
+                }
+                let convolutional = matches!(settings.interleaving, Interleaving::Convolutional { .. });
+                if ui.selectable_label(convolutional, "Convolutional").clicked() && !convolutional {
+                    settings.interleaving = Interleaving::Convolutional { branches: 4, depth: 2 };
+                    changed = true;
+                }
+            });
+
+        match &mut settings.interleaving {
+            Interleaving::Block { rows, columns } => {
+                ui.horizontal(|ui| {
+                    ui.label("Rows (depth):");
+                    changed |= ui.add(egui::DragValue::new(rows).clamp_range(1..=64)).changed();
+                    ui.label("Columns:");
+                    changed |= ui.add(egui::DragValue::new(columns).clamp_range(1..=256)).changed();
+                });
+            }
+            Interleaving::Convolutional { branches, depth } => {
+                ui.horizontal(|ui| {
+                    ui.label("Branches:");
+                    changed |= ui.add(egui::DragValue::new(branches).clamp_range(1..=32)).changed();
+                    ui.label("Cell depth:");
+                    changed |= ui.add(egui::DragValue::new(depth).clamp_range(1..=32)).changed();
+                });
+            }
+            Interleaving::None => {}
+        }
+        if settings.interleaving != Interleaving::None {
+            let padding = settings.interleaved_stream.len() - settings.protected_stream.len();
+            if padding > 0 && settings.interleaving.delay() > 0 {
+                ui.label(format!("{} trailing 0 bit(s) flush the delay lines", padding));
+            } else if padding > 0 {
+                ui.label(format!("Padded with {} trailing 0 bit(s) to fill the last matrix", padding));
+            }
+            ui.label("Interleaved Stream:");
+            ui.monospace(&settings.interleaved_stream);
+        }
+    });
+
+    ui.vertical(|ui| {
+        ui.label("Block Coding:");
+        ui.add_space(5.0);
+        egui::ComboBox::from_id_source(2)
+            .selected_text(format!("{:?}", settings.block_coding))
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut settings.block_coding, BlockCoding::None, "None").changed();
+                changed |= ui.selectable_value(&mut settings.block_coding, BlockCoding::FourBFiveB, "4B/5B").changed();
+                changed |= ui.selectable_value(&mut settings.block_coding, BlockCoding::EightBTenB, "8B/10B").changed();
+                changed |= ui.selectable_value(&mut settings.block_coding, BlockCoding::SixtyFourBSixtySixB, "64b/66b").changed();
+            });
+
+        if settings.block_coding != BlockCoding::None {
+            changed |= ui.checkbox(&mut settings.control_symbols, "Control symbols").changed();
+            let padding = settings.block_coding.padding(settings.interleaved_stream.len());
+            if padding > 0 {
+                ui.label(format!("Padded with {} trailing 0 bit(s) to fill the last block", padding));
+            }
+            ui.label("Coded Stream:");
+            ui.monospace(&settings.unscrambled_stream);
+        }
+    });
+
+    ui.vertical(|ui| {
+        changed |= ui.checkbox(&mut settings.lfsr_enabled, "LFSR Scrambler").changed();
+        if settings.lfsr_enabled {
+            ui.horizontal(|ui| {
+                ui.label("Polynomial:");
+                changed |= ui.text_edit_singleline(&mut settings.lfsr_polynomial).changed();
+            });
+            match Lfsr::parse_taps(&settings.lfsr_polynomial) {
+                Some(taps) => settings.lfsr.taps = taps,
+                None => {
+                    ui.label("Invalid polynomial, expected e.g. x^7 + x^4 + 1");
                 }
-            } else {
-                ui.label("Invalid input");
             }
+            ui.horizontal(|ui| {
+                ui.label("Seed:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.lfsr.seed)).changed();
+                changed |= ui.radio_value(&mut settings.lfsr.mode, LfsrMode::Additive, "Additive").changed();
+                changed |= ui.radio_value(&mut settings.lfsr.mode, LfsrMode::SelfSynchronizing, "Self-synchronizing").changed();
+            });
+            ui.label("Original:");
+            ui.monospace(&settings.unscrambled_stream);
+            ui.label("Scrambled:");
+            ui.monospace(&settings.coded_stream);
         }
     });
 
@@ -34,39 +678,530 @@ pub fn draw_ui(ui: &mut egui::Ui, settings: &mut DigitalSettings) {
         egui::ComboBox::from_id_source(0)
             .selected_text(format!("{:?}", settings.encoding))
             .show_ui(ui, |ui| {
-                for encoding in vec!["NRZ-L", "NRZ-I", "Manchester", "Differential Manchester", "AMI"] {
-                    if ui.selectable_label(false, encoding).clicked() {
-                        settings.encoding = match encoding {
-                            "NRZ-L" => Box::new(NRZL),
-                            "NRZ-I" => Box::new(NRZI),
-                            "Manchester" => Box::new(Manchester),
-                            "Differential Manchester" => Box::new(ManchesterDifferential),
-                            "AMI" => Box::new(AMI),
-                            _ => unimplemented!(),
-                        };
-                    
+                for name in ENCODINGS {
+                    if ui.selectable_label(false, name).clicked() {
+                        settings.encoding = encoding(name).unwrap();
+                        changed = true;
                     }
                 }
             });
+        changed |= settings.encoding.draw_options(ui);
     });
 
-    if format!("{:?}", settings.encoding) == "AMI" {
+    let padding = settings.encoding.padding(settings.coded_stream.len());
+    if padding > 0 {
+        ui.label(format!("Padded with {} trailing 0 bit(s) to fill the last symbol", padding));
+    }
+
+    if settings.encoding.is_bipolar() {
         ui.vertical(|ui| {
             ui.label("Scrambling:");
             ui.add_space(5.0);
             egui::ComboBox::from_id_source(1)
                 .selected_text(format!("{:?}", settings.scrambling))
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut settings.scrambling, Scrambling::None, "None");
-                    ui.selectable_value(&mut settings.scrambling, Scrambling::B8ZS, "B8ZS");
-                    ui.selectable_value(&mut settings.scrambling, Scrambling::HDB3, "HDB3");
+                    changed |= ui.selectable_value(&mut settings.scrambling, Scrambling::None, "None").changed();
+                    changed |= ui.selectable_value(&mut settings.scrambling, Scrambling::B3ZS, "B3ZS").changed();
+                    changed |= ui.selectable_value(&mut settings.scrambling, Scrambling::B6ZS, "B6ZS").changed();
+                    changed |= ui.selectable_value(&mut settings.scrambling, Scrambling::B8ZS, "B8ZS").changed();
+                    changed |= ui.selectable_value(&mut settings.scrambling, Scrambling::HDB3, "HDB3").changed();
                 });
         });
     }
 
+    ui.vertical(|ui| {
+        changed |= ui.checkbox(&mut settings.channel.enabled, "AWGN Channel").changed();
+        if settings.channel.enabled {
+            changed |= ui.add(egui::Slider::new(&mut settings.channel.eb_n0, -5.0..=20.0).text("Eb/N0 (dB)")).changed();
+            ui.horizontal(|ui| {
+                ui.label("Seed:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.channel.seed)).changed();
+                ui.label("Samples per element:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.channel.samples).clamp_range(1..=64)).changed();
+            });
+            let errors = settings
+                .decoded_stream
+                .chars()
+                .zip(settings.coded_stream.chars())
+                .filter(|(decoded, sent)| decoded != sent)
+                .count();
+            ui.label(format!("{} bit error(s) on the line", errors));
+        }
+    });
+
+    ui.collapsing("Eye Diagram", |ui| {
+        let eye = &mut settings.eye;
+        changed |= ui.checkbox(&mut eye.enabled, "Show the eye instead of the trace").changed();
+        if !settings.channel.enabled {
+            ui.horizontal(|ui| {
+                ui.label("Samples per element:");
+                changed |= ui.add(egui::DragValue::new(&mut settings.channel.samples).clamp_range(1..=64)).changed();
+            });
+        }
+        changed |= ui.checkbox(&mut eye.filter, "Low-pass filter").changed();
+        if eye.filter {
+            changed |= ui.add(egui::Slider::new(&mut eye.bandwidth, 0.1..=2.0).text("Cut-off (x element rate)")).changed();
+        }
+        if !eye.enabled {
+            return;
+        }
+        // One unit interval is one element, which is less than a bit for the
+        // codes with half-bit pulses and more for the multilevel ones.
+        let bits = settings.encoding.bits_per_symbol() as f32 / settings.encoding.elements_per_symbol() as f32;
+        let interval = |intervals: f32| {
+            if bits == 1.0 {
+                format!("{:.2} UI", intervals)
+            } else {
+                format!("{:.2} UI ({:.2} bit periods)", intervals, intervals * bits)
+            }
+        };
+        ui.label("1 UI is one signal element on the line");
+        match &eye.measurement {
+            Some(measurement) if measurement.height > 0.0 => {
+                let percent = 100.0 * measurement.height / measurement.spacing;
+                ui.label(format!("Eye height: {:.2} of {} ({:.0}%)", measurement.height, measurement.spacing, percent));
+                ui.label(format!("Eye width: {}", interval(measurement.width)));
+                let phase = (measurement.phase as f32 + 0.5) / eye.samples as f32;
+                ui.label(format!("Best sampling instant: {:.2} UI", phase));
+            }
+            Some(_) => {
+                ui.colored_label(egui::Color32::RED, "The eye is closed");
+                if settings.channel.enabled && !eye.filter {
+                    ui.label("Without the filter every sample carries the full noise");
+                }
+            }
+            None => {
+                ui.label("The eye needs at least two levels on the line");
+            }
+        }
+        if let Some(measurement) = &eye.measurement {
+            match (measurement.jitter, measurement.jitter_rms) {
+                (Some(jitter), Some(rms)) => ui.label(format!("Jitter: {} peak to peak, {} RMS", interval(jitter), interval(rms))),
+                _ => ui.label("Jitter: no transitions"),
+            };
+        }
+    });
+
+    ui.collapsing("BER Simulation", |ui| {
+        let ber = &mut settings.ber;
+        ui.horizontal(|ui| {
+            ui.label("Eb/N0 from");
+            ui.add(egui::DragValue::new(&mut ber.from).speed(0.5));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut ber.to).speed(0.5));
+            ui.label("dB, step");
+            ui.add(egui::DragValue::new(&mut ber.step).speed(0.1).clamp_range(0.1..=10.0));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Bits per point:");
+            ui.add(egui::DragValue::new(&mut ber.bits).speed(1000).clamp_range(100..=1_000_000));
+            ui.label("Seed:");
+            ui.add(egui::DragValue::new(&mut ber.seed));
+        });
+        ui.label("Only the line code, the channel and a threshold decoder take part.");
+        let name = format!("{:?}", settings.encoding);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(ber.running.is_none(), egui::Button::new(format!("Run {}", name))).clicked() {
+                ber.running = Some(Sweep::new(name.clone(), ber));
+            }
+            if ui.button("Clear").clicked() {
+                ber.curves.clear();
+            }
+            ui.checkbox(&mut ber.show_plot, "Show plot");
+        });
+        if let Some(sweep) = &ber.running {
+            let stop = ui
+                .horizontal(|ui| {
+                    ui.label(format!("Simulating {}: {:.0}%", sweep.name, 100.0 * sweep.progress()));
+                    ui.button("Stop").clicked()
+                })
+                .inner;
+            if stop {
+                ber.running = None;
+            }
+        }
+    });
+
+    ui.collapsing("Decoder", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Flip received bits:");
+            changed |= ui.text_edit_singleline(&mut settings.bit_flips).changed();
+        });
+        match parse_positions(&settings.bit_flips) {
+            Some(flips) => settings.flips = flips,
+            None => {
+                ui.label("Invalid positions, expected e.g. 0, 5, 12");
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("Erase received bits:");
+            changed |= ui.text_edit_singleline(&mut settings.bit_erasures).changed();
+        });
+        match parse_positions(&settings.bit_erasures) {
+            Some(erasures) => settings.erasures = erasures,
+            None => {
+                ui.label("Invalid positions, expected e.g. 0, 5, 12");
+            }
+        }
+
+        ui.label("Decoded Stream:");
+        draw_comparison(ui, &settings.decoded_stream, &settings.coded_stream);
+        ui.label("Decoded Message:");
+        draw_comparison(ui, &settings.decoded_message, &settings.binary_stream);
+        if settings.decode_errors.is_empty() {
+            ui.label("Decoded without errors");
+        }
+        for correction in &settings.corrections {
+            ui.colored_label(egui::Color32::YELLOW, correction);
+        }
+        for error in &settings.decode_errors {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        if settings.encoding.is_bipolar() {
+            let count = |kind| settings.violations.iter().filter(|(_, v)| *v == kind).count();
+            ui.label(format!(
+                "Violations: {} V, {} B, {} unexplained",
+                count(Violation::Bipolar),
+                count(Violation::Balancing),
+                count(Violation::Error)
+            ));
+        }
+    });
+
     ui.vertical(|ui| {
         ui.label("Longest Palindrome:");
         ui.add_space(5.0);
         ui.text_edit_singleline(&mut settings.longest_palindrome).changed();
     });
+
+    if changed && crate::utils::validate_input(&settings.binary_stream) {
+        encode(settings);
+        decode(settings);
+    }
+}
+
+/// Draws every branch of the trellis in grey, dashed where the input is a one,
+/// and the path the Viterbi decoder settled on in green.
+fn draw_trellis(ui: &mut egui::Ui, code: &Convolutional, path: &[usize]) {
+    const MAX_STEPS: usize = 32;
+    const MAX_STATES: usize = 16;
+    let steps = path.len().saturating_sub(1);
+    if steps == 0 || steps > MAX_STEPS || code.states() > MAX_STATES {
+        ui.label(format!(
+            "The trellis is drawn for up to {} steps and {} states",
+            MAX_STEPS, MAX_STATES
+        ));
+        return;
+    }
+
+    let spacing = egui::vec2(24.0, 20.0);
+    let size = egui::vec2(40.0 + spacing.x * steps as f32, spacing.y * code.states() as f32);
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let node = |step: usize, state: usize| {
+        response.rect.min + egui::vec2(40.0 + spacing.x * step as f32, spacing.y * (state as f32 + 0.5))
+    };
+
+    for state in 0..code.states() {
+        let label = format!("{:0width$b}", state, width = code.constraint - 1);
+        let position = node(0, state) - egui::vec2(30.0, 0.0);
+        painter.text(position, egui::Align2::LEFT_CENTER, label, egui::TextStyle::Monospace, egui::Color32::GRAY);
+        for step in 0..steps {
+            for input in [false, true] {
+                let (_, next) = code.branch(state, input);
+                let (from, to) = (node(step, state), node(step + 1, next));
+                let stroke = (1.0, egui::Color32::from_gray(90));
+                if input {
+                    for i in 0..4 {
+                        let t = i as f32 / 4.0;
+                        painter.line_segment([from + (to - from) * t, from + (to - from) * (t + 0.125)], stroke);
+                    }
+                } else {
+                    painter.line_segment([from, to], stroke);
+                }
+            }
+        }
+    }
+    for (step, states) in path.windows(2).enumerate() {
+        let stroke = (3.0, egui::Color32::LIGHT_GREEN);
+        painter.line_segment([node(step, states[0]), node(step + 1, states[1])], stroke);
+    }
+    for step in 0..=steps {
+        for state in 0..code.states() {
+            painter.circle_filled(node(step, state), 3.0, egui::Color32::WHITE);
+        }
+    }
+}
+
+/// Parses bit positions separated by commas or spaces.
+fn parse_positions(positions: &str) -> Option<Vec<usize>> {
+    positions
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|position| !position.is_empty())
+        .map(|position| position.parse().ok())
+        .collect()
+}
+
+/// Prints `decoded` bit by bit, red where it differs from `expected` and grey where
+/// it runs past it into padding.
+fn draw_comparison(ui: &mut egui::Ui, decoded: &str, expected: &str) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        let mut expected = expected.chars();
+        for c in decoded.chars() {
+            let color = match expected.next() {
+                Some(e) if e == c => egui::Color32::LIGHT_GREEN,
+                Some(_) => egui::Color32::RED,
+                None => egui::Color32::GRAY,
+            };
+            ui.add(egui::Label::new(c).monospace().text_color(color));
+        }
+    });
+}
+
+pub fn view(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    if settings.eye.enabled {
+        draw_eye(settings, app, draw);
+        draw_interleaving(settings, app, draw);
+        return;
+    }
+    draw_blocks(settings, app, draw);
+    draw_highlights(settings, app, draw);
+    draw_flips(settings, app, draw);
+    settings.encoding.draw_encoding(&settings.result, app, draw);
+    draw_waveform(settings, app, draw);
+    draw_violations(settings, app, draw);
+    draw_interleaving(settings, app, draw);
+}
+
+/// Draws the bits in error before and after the deinterleaver in two rows along
+/// the bottom of the window, joined by a line, with the codewords marked on the
+/// lower row, so a burst on the line shows up spread over several codewords.
+fn draw_interleaving(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    if settings.interleaving == Interleaving::None || settings.protected_stream.is_empty() {
+        return;
+    }
+    let win = app.main_window().rect();
+    let (received_y, deinterleaved_y) = (-290.0, -350.0);
+    let received_length = win.w() / settings.interleaved_stream.len() as f32;
+    let deinterleaved_length = win.w() / settings.protected_stream.len() as f32;
+
+    for (y, label) in [(received_y, "Received"), (deinterleaved_y, "Deinterleaved")] {
+        draw.line()
+            .points(pt2(win.left(), y), pt2(win.right(), y))
+            .weight(1.0)
+            .color(LIGHTGRAY);
+        draw.text(label)
+            .x_y(win.left() + 60.0, y + 12.0)
+            .font_size(12)
+            .color(LIGHTGRAY);
+    }
+    if let Some(codeword) = settings.channel_coding.codeword_bits() {
+        for start in (0..=settings.protected_stream.len()).step_by(codeword) {
+            let x = win.left() + deinterleaved_length * start as f32;
+            draw.line()
+                .points(pt2(x, deinterleaved_y - 8.0), pt2(x, deinterleaved_y + 8.0))
+                .weight(2.0)
+                .color(LIGHTGRAY);
+        }
+    }
+
+    let sources: Vec<Option<usize>> = (0..settings.protected_stream.len()).map(Some).collect();
+    let sources = interleave(&sources, settings.interleaving, None);
+    for &i in &settings.channel_errors {
+        let from = pt2(win.left() + received_length * (i as f32 + 0.5), received_y);
+        draw.ellipse().xy(from).radius(3.0).color(RED);
+        if let Some(Some(source)) = sources.get(i) {
+            let to = pt2(win.left() + deinterleaved_length * (*source as f32 + 0.5), deinterleaved_y);
+            draw.line().points(from, to).weight(1.0).color(rgba(1.0, 0.0, 0.0, 0.5));
+            draw.ellipse().xy(to).radius(3.0).color(RED);
+        }
+    }
+}
+
+/// Traces the noisy waveform over the clean levels.
+fn draw_waveform(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    if settings.waveform.is_empty() {
+        return;
+    }
+    let win = app.main_window().rect();
+    let sample_length = win.w() / settings.waveform.len() as f32;
+    let level_height = settings.encoding.level_height();
+    let points = settings.waveform.iter().enumerate().map(|(i, &x)| {
+        let x_position = win.left() + sample_length * (i as f32 + 0.5);
+        pt2(x_position, x * level_height)
+    });
+    draw.polyline().weight(1.5).points(points).color(rgba(1.0, 0.6, 0.0, 0.8));
+}
+
+/// Folds the waveform over two unit intervals, from the middle of one element to
+/// the middle of the element after next, so the eye sits in the centre. The
+/// traces are faint, so the paths the signal takes most often show brightest.
+fn draw_eye(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    let eye = &settings.eye;
+    let samples = eye.samples;
+    let offset = samples / 2;
+    let win = app.main_window().rect();
+    let interval = (win.w() - 100.0) / 2.0;
+    let x = |time: f32| win.left() + 50.0 + interval * time.clamp(0.0, 2.0);
+    let levels = settings.encoding.levels();
+    let max_level = levels.iter().map(|level| level.abs()).max().unwrap_or(1).max(1);
+    let scale = 200.0 / max_level as f32;
+
+    for level in &levels {
+        let y = *level as f32 * scale;
+        draw.line()
+            .weight(1.0)
+            .points(pt2(x(0.0), y), pt2(x(2.0), y))
+            .color(rgba(1.0, 1.0, 1.0, 0.3));
+        draw.text(&level.to_string())
+            .x_y(x(0.0) - 15.0, y)
+            .font_size(10)
+            .color(LIGHTGRAY);
+    }
+    for i in 0..=2 {
+        let time = i as f32;
+        draw.line()
+            .weight(1.0)
+            .points(pt2(x(time), -250.0), pt2(x(time), 250.0))
+            .color(LIGHTGRAY);
+        draw.text(&format!("{} UI", i))
+            .x_y(x(time), -262.0)
+            .font_size(12)
+            .color(LIGHTGRAY);
+    }
+
+    let starts: Vec<usize> = (0..)
+        .map(|element| element * samples + offset)
+        .take_while(|&start| start + 2 * samples < eye.waveform.len())
+        .collect();
+    let alpha = (20.0 / starts.len().max(1) as f32).clamp(0.03, 0.6);
+    for start in starts {
+        let points = eye.waveform[start..=start + 2 * samples].iter().enumerate().map(|(i, &value)| {
+            let time = ((offset + i) as f32 + 0.5) / samples as f32 - 0.5;
+            pt2(x(time), value * scale)
+        });
+        draw.polyline().weight(1.5).points(points).color(rgba(0.2, 0.9, 0.4, alpha));
+    }
+
+    let measurement = match &eye.measurement {
+        Some(measurement) if measurement.height > 0.0 => measurement,
+        _ => return,
+    };
+    let (bottom, top) = measurement.opening;
+    let instant = x(0.5 + (measurement.phase as f32 + 0.5) / samples as f32);
+    draw.line()
+        .weight(2.0)
+        .points(pt2(instant, bottom * scale), pt2(instant, top * scale))
+        .color(YELLOW);
+    let threshold = (bottom + top) / 2.0 * scale;
+    let open_from = 0.5 + measurement.open_from as f32 / samples as f32;
+    draw.line()
+        .weight(2.0)
+        .points(pt2(x(open_from), threshold), pt2(x(open_from + measurement.width), threshold))
+        .color(YELLOW);
+    draw.text(&format!("{:.2}", measurement.height))
+        .x_y(instant + 20.0, top * scale - 10.0)
+        .font_size(12)
+        .color(YELLOW);
+    draw.text(&format!("{:.2} UI", measurement.width))
+        .x_y(x(open_from + measurement.width / 2.0), threshold + 12.0)
+        .font_size(12)
+        .color(YELLOW);
+}
+
+/// Marks the V and B pulses of substitutions and any unexplained violation above
+/// (or below) the pulse they belong to.
+fn draw_violations(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    let win = app.main_window().rect();
+    let elements = settings.encoding.elements_per_symbol();
+    let element_length = win.w() / settings.result.len() as f32;
+    let level_height = settings.encoding.level_height();
+    for &(i, violation) in &settings.violations {
+        let x = win.left() + element_length * ((i * elements) as f32 + 0.5);
+        let level = settings.received[i * elements] as f32;
+        let y = level * level_height + level.signum() * 30.0;
+        let (label, color) = match violation {
+            Violation::Bipolar => ("V", ORANGE),
+            Violation::Balancing => ("B", LIGHTGREEN),
+            Violation::Error => ("!", RED),
+        };
+        draw.ellipse().x_y(x, y).radius(10.0).color(color);
+        draw.text(label).x_y(x, y).font_size(12).color(BLACK);
+    }
+}
+
+/// Width on the canvas of one bit of the stream that reaches the line code.
+fn bit_length(settings: &DigitalSettings, win: &Rect) -> Option<f32> {
+    let encoding = &settings.encoding;
+    let bits = settings.result.len() * encoding.bits_per_symbol() / encoding.elements_per_symbol();
+    if bits == 0 {
+        return None;
+    }
+    Some(win.w() / bits as f32)
+}
+
+fn draw_highlights(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    let win = app.main_window().rect();
+    let bit_length = match bit_length(settings, &win) {
+        Some(bit_length) => bit_length,
+        None => return,
+    };
+    for highlight in &settings.highlights {
+        let width = bit_length * highlight.bits.len() as f32;
+        let x = win.left() + bit_length * highlight.bits.start as f32 + width / 2.0;
+        draw.rect().x_y(x, 0.0).w_h(width, 400.0).color(highlight.color);
+    }
+}
+
+/// Marks the received bits that the bit-flip tool inverts or erases.
+fn draw_flips(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    let win = app.main_window().rect();
+    let bit_length = match bit_length(settings, &win) {
+        Some(bit_length) => bit_length,
+        None => return,
+    };
+    for &i in settings.flips.iter().filter(|&&i| i < settings.coded_stream.len()) {
+        let x = win.left() + bit_length * (i as f32 + 0.5);
+        draw.rect().x_y(x, 0.0).w_h(bit_length, 400.0).no_fill().stroke(RED).stroke_weight(2.0);
+        draw.text("X").x_y(x, 200.0).font_size(14).color(RED);
+    }
+    for &i in settings.erasures.iter().filter(|&&i| i < settings.coded_stream.len()) {
+        let x = win.left() + bit_length * (i as f32 + 0.5);
+        draw.rect().x_y(x, 0.0).w_h(bit_length, 400.0).color(rgba(0.5, 0.5, 0.5, 0.4));
+        draw.text("?").x_y(x, 185.0).font_size(14).color(GRAY);
+    }
+}
+
+/// Shades every code group behind the waveform and names its symbol underneath.
+fn draw_blocks(settings: &DigitalSettings, app: &App, draw: &Draw) {
+    let win = app.main_window().rect();
+    let bit_length = match bit_length(settings, &win) {
+        Some(bit_length) => bit_length,
+        None => return,
+    };
+    let mut start = 0;
+    for (i, block) in settings.blocks.iter().enumerate() {
+        let width = bit_length * block.bits.len() as f32;
+        let x = win.left() + bit_length * start as f32 + width / 2.0;
+        let color = if block.control {
+            rgba(1.0, 0.65, 0.0, 0.2)
+        } else if i % 2 == 0 {
+            rgba(0.27, 0.51, 0.71, 0.25)
+        } else {
+            rgba(0.27, 0.51, 0.71, 0.1)
+        };
+        draw.rect().x_y(x, 0.0).w_h(width, 400.0).color(color);
+        if block.header > 0 {
+            let header = bit_length * block.header as f32;
+            let header_x = win.left() + bit_length * start as f32 + header / 2.0;
+            draw.rect().x_y(header_x, 0.0).w_h(header, 400.0).color(rgba(0.6, 0.2, 0.8, 0.35));
+        }
+        draw.text(&block.label).x_y(x, -220.0).font_size(12).color(WHITE);
+        if let Some(disparity) = block.disparity {
+            let rd = if disparity < 0 { "RD-" } else { "RD+" };
+            draw.text(rd).x_y(x, -240.0).font_size(12).color(LIGHTGRAY);
+        }
+        start += block.bits.len();
+    }
 }
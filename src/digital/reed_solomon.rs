@@ -0,0 +1,361 @@
+use super::fec::Correction;
+use std::fmt::{self, Debug};
+
+/// Reed-Solomon code over GF(2^8) with `n - k` parity symbols per codeword,
+/// generated by the roots α^0 to α^(n-k-1) where α is a root of
+/// x^8 + x^4 + x^3 + x^2 + 1. Codes with n below 255 are shortened, and so is a
+/// final codeword that carries fewer than k symbols.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ReedSolomon {
+    pub n: usize,
+    pub k: usize,
+}
+
+impl Debug for ReedSolomon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RS({},{})", self.n, self.k)
+    }
+}
+
+impl Default for ReedSolomon {
+    /// The outer code of DVB.
+    fn default() -> Self {
+        ReedSolomon { n: 204, k: 188 }
+    }
+}
+
+/// Exponential and logarithm tables of GF(2^8), the former doubled so products
+/// need no reduction.
+struct Field {
+    exp: [u8; 512],
+    log: [usize; 256],
+}
+
+/// Built once at compile time and shared by every code.
+static FIELD: Field = Field::new();
+
+impl Field {
+    const fn new() -> Self {
+        let mut field = Field {
+            exp: [0; 512],
+            log: [0; 256],
+        };
+        let mut x = 1usize;
+        let mut i = 0;
+        while i < 255 {
+            field.exp[i] = x as u8;
+            field.log[x] = i;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+            i += 1;
+        }
+        while i < 512 {
+            field.exp[i] = field.exp[i - 255];
+            i += 1;
+        }
+        field
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] + self.log[b as usize]]
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] + 255 - self.log[b as usize]]
+    }
+
+    fn pow(&self, power: usize) -> u8 {
+        self.exp[power % 255]
+    }
+
+    fn inverse(&self, a: u8) -> u8 {
+        self.div(1, a)
+    }
+
+    // Polynomials are stored with the highest power first.
+
+    fn eval(&self, p: &[u8], x: u8) -> u8 {
+        p.iter().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    fn poly_mul(&self, p: &[u8], q: &[u8]) -> Vec<u8> {
+        let mut product = vec![0; p.len() + q.len() - 1];
+        for (i, &a) in p.iter().enumerate() {
+            for (j, &b) in q.iter().enumerate() {
+                product[i + j] ^= self.mul(a, b);
+            }
+        }
+        product
+    }
+
+    fn poly_add(&self, p: &[u8], q: &[u8]) -> Vec<u8> {
+        let mut sum = vec![0; p.len().max(q.len())];
+        let len = sum.len();
+        for (i, &c) in p.iter().enumerate() {
+            sum[i + len - p.len()] = c;
+        }
+        for (i, &c) in q.iter().enumerate() {
+            sum[i + len - q.len()] ^= c;
+        }
+        sum
+    }
+
+    fn poly_scale(&self, p: &[u8], x: u8) -> Vec<u8> {
+        p.iter().map(|&c| self.mul(c, x)).collect()
+    }
+}
+
+impl ReedSolomon {
+    pub fn parity(&self) -> usize {
+        self.n - self.k
+    }
+
+    fn generator(&self, field: &Field) -> Vec<u8> {
+        (0..self.parity()).fold(vec![1], |g, i| field.poly_mul(&g, &[1, field.pow(i)]))
+    }
+
+    /// Appends the parity symbols to every k message symbols.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let field = &FIELD;
+        let generator = self.generator(field);
+        let mut coded = Vec::with_capacity(data.len() / self.k * self.n + self.n);
+        for message in data.chunks(self.k) {
+            let mut remainder = message.to_vec();
+            remainder.resize(message.len() + self.parity(), 0);
+            for i in 0..message.len() {
+                let coefficient = remainder[i];
+                for (j, &g) in generator.iter().enumerate().skip(1) {
+                    remainder[i + j] ^= field.mul(g, coefficient);
+                }
+            }
+            coded.extend_from_slice(message);
+            coded.extend_from_slice(&remainder[message.len()..]);
+        }
+        coded
+    }
+
+    /// Corrects every codeword of `received` and returns the message symbols.
+    /// Symbols listed in `erasures` are known to be unreliable, which lets the
+    /// code fix up to n - k of them instead of (n - k) / 2 unknown errors.
+    pub fn decode(&self, received: &[u8], erasures: &[usize]) -> (Vec<u8>, Correction) {
+        let field = &FIELD;
+        let mut data = Vec::with_capacity(received.len());
+        let mut correction = Correction::default();
+        for (i, codeword) in received.chunks(self.n).enumerate() {
+            if codeword.len() <= self.parity() {
+                correction.errors.push(format!("Codeword {} is too short", i));
+                break;
+            }
+            let start = i * self.n;
+            let erased: Vec<usize> = erasures
+                .iter()
+                .filter(|&&p| p >= start && p < start + codeword.len())
+                .map(|&p| p - start)
+                .collect();
+            let message = codeword.len() - self.parity();
+            match self.correct(field, codeword, &erased) {
+                Some(corrected) => {
+                    // Erased symbols that held the right value were not corrected.
+                    for (j, (a, b)) in codeword.iter().zip(&corrected).enumerate() {
+                        if a != b {
+                            correction.corrected.push(format!("Corrected symbol {} of codeword {}", j, i));
+                        }
+                    }
+                    data.extend_from_slice(&corrected[..message]);
+                }
+                None => {
+                    correction.errors.push(format!("Codeword {} has too many errors", i));
+                    data.extend_from_slice(&codeword[..message]);
+                }
+            }
+        }
+        (data, correction)
+    }
+
+    fn syndromes(&self, field: &Field, codeword: &[u8]) -> Vec<u8> {
+        (0..self.parity()).map(|i| field.eval(codeword, field.pow(i))).collect()
+    }
+
+    /// Berlekamp-Massey on the syndromes with the erasures taken out, a Chien
+    /// search for the error positions and Forney's algorithm for the values of
+    /// errors and erasures alike.
+    fn correct(&self, field: &Field, codeword: &[u8], erased: &[usize]) -> Option<Vec<u8>> {
+        let parity = self.parity();
+        if erased.len() > parity {
+            return None;
+        }
+        let mut codeword = codeword.to_vec();
+        for &p in erased {
+            codeword[p] = 0;
+        }
+        let syndromes = self.syndromes(field, &codeword);
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(codeword);
+        }
+
+        // Forney syndromes, which no longer see the erasures.
+        let len = codeword.len();
+        let mut forney = syndromes.clone();
+        for &p in erased {
+            let x = field.pow(len - 1 - p);
+            for j in 0..forney.len() - 1 {
+                forney[j] = field.mul(forney[j], x) ^ forney[j + 1];
+            }
+        }
+
+        let mut locator = vec![1];
+        let mut old = vec![1];
+        for i in 0..parity - erased.len() {
+            let mut delta = forney[i];
+            for j in 1..locator.len().min(i + 1) {
+                delta ^= field.mul(locator[locator.len() - 1 - j], forney[i - j]);
+            }
+            old.push(0);
+            if delta != 0 {
+                if old.len() > locator.len() {
+                    let new = field.poly_scale(&old, delta);
+                    old = field.poly_scale(&locator, field.inverse(delta));
+                    locator = new;
+                }
+                locator = field.poly_add(&locator, &field.poly_scale(&old, delta));
+            }
+        }
+        while locator.first() == Some(&0) {
+            locator.remove(0);
+        }
+        let errors = locator.len() - 1;
+        if 2 * errors + erased.len() > parity {
+            return None;
+        }
+
+        locator.reverse();
+        let mut positions: Vec<usize> = (0..len)
+            .filter(|&i| field.eval(&locator, field.pow(i)) == 0)
+            .map(|i| len - 1 - i)
+            .collect();
+        if positions.len() != errors {
+            return None;
+        }
+        positions.extend_from_slice(erased);
+
+        let powers: Vec<usize> = positions.iter().map(|&p| len - 1 - p).collect();
+        let errata = powers
+            .iter()
+            .fold(vec![1], |l, &power| field.poly_mul(&l, &[field.pow(power), 1]));
+        // Error evaluator, the syndrome polynomial times the errata locator modulo
+        // x^(errata + 1).
+        let mut syndrome_polynomial: Vec<u8> = syndromes.iter().rev().copied().collect();
+        syndrome_polynomial.push(0);
+        let product = field.poly_mul(&syndrome_polynomial, &errata);
+        let evaluator = &product[product.len().saturating_sub(errata.len())..];
+
+        let x: Vec<u8> = powers.iter().map(|&power| field.pow(power)).collect();
+        for (i, &xi) in x.iter().enumerate() {
+            let xi_inverse = field.inverse(xi);
+            let derivative = x
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(1, |acc, (_, &xj)| field.mul(acc, 1 ^ field.mul(xi_inverse, xj)));
+            if derivative == 0 {
+                return None;
+            }
+            let y = field.mul(xi, field.eval(evaluator, xi_inverse));
+            codeword[positions[i]] ^= field.div(y, derivative);
+        }
+
+        if self.syndromes(field, &codeword).iter().any(|&s| s != 0) {
+            return None;
+        }
+        Some(codeword)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::seq::index::sample;
+    use nannou::rand::{Rng, SeedableRng};
+
+    const CODES: [(usize, usize); 5] = [(255, 223), (204, 188), (15, 9), (10, 4), (255, 254)];
+
+    /// Three full codewords with `errors` random symbols changed and `erased`
+    /// more marked as erasures, some of them changed too, in every codeword.
+    fn damage(rng: &mut StdRng, code: ReedSolomon, errors: usize, erased: usize) -> (Vec<u8>, Vec<u8>, Vec<usize>, usize) {
+        let data: Vec<u8> = (0..3 * code.k).map(|_| rng.gen()).collect();
+        let mut received = code.encode(&data);
+        let mut erasures = Vec::new();
+        let mut changed = 0;
+        for start in (0..received.len()).step_by(code.n) {
+            let positions = sample(rng, code.n, errors + erased).into_vec();
+            for (i, &p) in positions.iter().enumerate() {
+                let value = if i < errors { received[start + p] ^ rng.gen_range(1..=255) } else { rng.gen() };
+                changed += (value != received[start + p]) as usize;
+                received[start + p] = value;
+                if i >= errors {
+                    erasures.push(start + p);
+                }
+            }
+        }
+        (data, received, erasures, changed)
+    }
+
+    #[test]
+    fn field_tables() {
+        assert_eq!(FIELD.exp[8], 0x1d);
+        assert_eq!(FIELD.mul(FIELD.pow(200), FIELD.pow(100)), FIELD.pow(45));
+        assert!((1..=255).all(|a| FIELD.mul(a, FIELD.inverse(a)) == 1));
+    }
+
+    #[test]
+    fn corrects_errors_and_erasures_within_capacity() {
+        let mut rng = StdRng::seed_from_u64(21);
+        for (n, k) in CODES {
+            let code = ReedSolomon { n, k };
+            let parity = code.parity();
+            for errors in 0..=parity / 2 {
+                let erased = parity - 2 * errors;
+                let (data, received, erasures, changed) = damage(&mut rng, code, errors, erased);
+                let (decoded, correction) = code.decode(&received, &erasures);
+                assert_eq!(decoded, data, "{:?} with {} errors and {} erasures", code, errors, erased);
+                assert!(correction.errors.is_empty());
+                assert_eq!(correction.corrected.len(), changed, "{:?}", code);
+            }
+        }
+    }
+
+    #[test]
+    fn shortened_final_codeword() {
+        let code = ReedSolomon { n: 15, k: 9 };
+        let data: Vec<u8> = (1..=12).collect();
+        let mut received = code.encode(&data);
+        assert_eq!(received.len(), 15 + 3 + 6);
+        received[16] ^= 0x55;
+        received[20] ^= 0x0f;
+        assert_eq!(code.decode(&received, &[]).0, data);
+    }
+
+    #[test]
+    fn fails_past_capacity() {
+        let mut rng = StdRng::seed_from_u64(21);
+        for (n, k) in CODES {
+            let code = ReedSolomon { n, k };
+            // One error too many can only ever be decoded to some other codeword.
+            let (data, received, _, _) = damage(&mut rng, code, code.parity() / 2 + 1, 0);
+            assert_ne!(code.decode(&received, &[]).0, data, "{:?}", code);
+            // Too many erasures are refused outright.
+            let (_, received, erasures, _) = damage(&mut rng, code, 0, code.parity() + 1);
+            let (_, correction) = code.decode(&received, &erasures);
+            assert_eq!(correction.errors.len(), 3, "{:?}", code);
+        }
+    }
+}
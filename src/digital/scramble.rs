@@ -1,92 +1,514 @@
-use super::encoding::AMI;
+use super::encoding::{BipolarRZ, Pseudoternary, AMI};
 
+/// Substitution scrambling for the bipolar codes. A code only has to say which
+/// bit it sends as a pulse; the other bit is sent as zero voltage.
 pub trait Scramble {
-    fn scramble(&self, data: &str, scheme: Scrambling) -> Vec<i8>;
+    fn mark(&self) -> char {
+        '1'
+    }
+
+    fn scramble(&self, data: &str, scheme: Scrambling) -> Vec<i8> {
+        match scheme {
+            Scrambling::B3ZS => b3zs(data, self.mark()),
+            Scrambling::B6ZS => b6zs(data, self.mark()),
+            Scrambling::B8ZS => b8zs(data, self.mark()),
+            Scrambling::HDB3 => hdb3(data, self.mark()),
+            Scrambling::None => alternate_mark(data, self.mark()),
+        }
+    }
+
+    fn descramble(&self, encoded: &[i8], scheme: Scrambling) -> String {
+        let pulses = match scheme {
+            Scrambling::B6ZS => pattern_pulses(encoded, b6zs_pattern),
+            Scrambling::B8ZS => pattern_pulses(encoded, b8zs_pattern),
+            Scrambling::B3ZS => parity_pulses(encoded, 3),
+            Scrambling::HDB3 => parity_pulses(encoded, 4),
+            Scrambling::None => encoded.iter().map(|&x| x != 0).collect(),
+        };
+        let space = if self.mark() == '1' { '0' } else { '1' };
+        pulses
+            .into_iter()
+            .map(|pulse| if pulse { self.mark() } else { space })
+            .collect()
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Scrambling {
+    B3ZS,
+    B6ZS,
     B8ZS,
     HDB3,
     None,
 }
 
-impl Scramble for AMI {
-    fn scramble(&self, data: &str, scheme: Scrambling) -> Vec<i8> {
-        match scheme {
-            Scrambling::B8ZS => b8zs(data),
-            Scrambling::HDB3 => hdb3(data),
-            Scrambling::None => unreachable!()
+/// A pulse that breaks the alternate mark inversion rule, or a pulse that was
+/// inserted together with such a violation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Violation {
+    /// The V pulse of a substitution.
+    Bipolar,
+    /// The B pulse of a substitution, which itself follows the alternation.
+    Balancing,
+    /// A violation that no substitution of the scheme explains.
+    Error,
+}
+
+impl Scramble for AMI {}
+
+impl Scramble for Pseudoternary {
+    fn mark(&self) -> char {
+        '0'
+    }
+}
+
+impl Scramble for BipolarRZ {}
+
+pub fn alternate_mark(data: &str, mark: char) -> Vec<i8> {
+    let mut toggle = 1;
+    data.chars().fold(Vec::new(), |mut acc, c| {
+        if c == mark {
+            acc.push(toggle);
+            toggle *= -1;
+        } else {
+            acc.push(0);
+        }
+        acc
+    })
+}
+
+/// B6ZS for DS2: every run of six zeros becomes 0VB0VB.
+pub fn b6zs_pattern(last: i8) -> Vec<i8> {
+    vec![0, last, -last, 0, -last, last]
+}
+
+/// B8ZS as in ANSI T1.403: every run of eight zeros becomes 000VB0VB.
+pub fn b8zs_pattern(last: i8) -> Vec<i8> {
+    vec![0, 0, 0, last, -last, 0, -last, last]
+}
+
+pub fn b6zs(data: &str, mark: char) -> Vec<i8> {
+    pattern_substitution(data, mark, b6zs_pattern)
+}
+
+pub fn b8zs(data: &str, mark: char) -> Vec<i8> {
+    pattern_substitution(data, mark, b8zs_pattern)
+}
+
+/// Replaces every run of zeros as long as the pattern with the pattern for the
+/// polarity of the preceding pulse. V has that polarity and B the opposite one; a
+/// run before the first mark treats the preceding pulse as negative.
+fn pattern_substitution(data: &str, mark: char, pattern: fn(i8) -> Vec<i8>) -> Vec<i8> {
+    let mut encoded_data = Vec::with_capacity(data.len());
+    let run = pattern(1).len();
+    let mut zeros = 0;
+    let mut last = -1;
+
+    for bit in data.chars() {
+        if bit == mark {
+            last = -last;
+            encoded_data.push(last);
+            zeros = 0;
+            continue;
+        }
+
+        encoded_data.push(0);
+        zeros += 1;
+        if zeros == run {
+            let start = encoded_data.len() - run;
+            encoded_data[start..].copy_from_slice(&pattern(last));
+            zeros = 0;
         }
     }
+
+    encoded_data
 }
 
-pub fn b8zs(data: &str) -> Vec<i8> {
-    let mut encoded_data = Vec::new();
-    let mut count = 0;
-    let mut toggle = -1;
+/// Which levels are genuine marks once every pattern substitution is taken out.
+pub fn pattern_pulses(encoded: &[i8], pattern: fn(i8) -> Vec<i8>) -> Vec<bool> {
+    let mut pulses = Vec::with_capacity(encoded.len());
+    let mut last = -1;
+    let mut i = 0;
+    while i < encoded.len() {
+        let substitution = pattern(last);
+        if encoded[i..].starts_with(&substitution) {
+            pulses.extend(substitution.iter().map(|_| false));
+            i += substitution.len();
+            continue;
+        }
+        if encoded[i] != 0 {
+            last = encoded[i];
+        }
+        pulses.push(encoded[i] != 0);
+        i += 1;
+    }
+    pulses
+}
 
-    for bit in data.chars().into_iter() {
-        match bit {
-            '0' => {
-                encoded_data.push(0);
-                count += 1;
-                if count == 8 {
-                    count = 0;
-                    encoded_data.splice(
-                        (encoded_data.len() - 8)..encoded_data.len(),
-                        [0, 0, 0, toggle, -toggle, 0, -toggle, toggle],
-                    );
-                }
-            }
-            '1' => {
-                toggle *= -1;
-                encoded_data.push(toggle);
-                count = 0;
+/// B3ZS for DS3: every run of three zeros becomes 00V or B0V.
+pub fn b3zs(data: &str, mark: char) -> Vec<i8> {
+    parity_substitution(data, mark, 3)
+}
+
+/// HDB3 as in ITU-T G.703: every run of four zeros becomes 000V or B00V.
+pub fn hdb3(data: &str, mark: char) -> Vec<i8> {
+    parity_substitution(data, mark, 4)
+}
+
+/// Replaces every run of `run` zeros with 0..0V when an odd number of pulses was
+/// sent since the last V and with B0..0V when it was even, so successive V pulses
+/// alternate in polarity. B follows the alternation and V repeats the polarity of
+/// the pulse before it. The count starts even, so a leading run gets a B.
+fn parity_substitution(data: &str, mark: char, run: usize) -> Vec<i8> {
+    let mut encoded_data = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    let mut last = -1;
+    let mut pulses = 0;
+
+    for bit in data.chars() {
+        if bit == mark {
+            last = -last;
+            encoded_data.push(last);
+            pulses += 1;
+            zeros = 0;
+            continue;
+        }
+
+        encoded_data.push(0);
+        zeros += 1;
+        if zeros == run {
+            let start = encoded_data.len() - run;
+            if pulses % 2 == 0 {
+                last = -last;
+                encoded_data[start] = last;
             }
-            _ => {}
+            encoded_data[start + run - 1] = last;
+            pulses = 0;
+            zeros = 0;
         }
     }
 
     encoded_data
 }
 
-pub fn hdb3(data: &str) -> Vec<i8> {
-    let mut encoded_data = Vec::new();
-    let mut count = 0;
-    let mut toggle = -1;
-    let mut non_zero_voltage_bool = true;
-    
-    for bit in data.chars().into_iter() {
-        match bit {
-            '0' => {
-                encoded_data.push(0);
-                count += 1;
-                if count == 4 {
-                    count = 0;
-                    let seq;
-                    if non_zero_voltage_bool {
-                        seq = vec![-toggle, 0, 0, -toggle];
-                        toggle *= -1;
+/// Which levels are genuine marks once every violation is taken out together with
+/// the `run - 1` positions before it, which covers both 0..0V and B0..0V.
+pub fn parity_pulses(encoded: &[i8], run: usize) -> Vec<bool> {
+    let mut pulses = Vec::with_capacity(encoded.len());
+    let mut last = -1;
+    for (i, &x) in encoded.iter().enumerate() {
+        if x == 0 {
+            pulses.push(false);
+            continue;
+        }
+        if x == last {
+            pulses[i.saturating_sub(run - 1)..].iter_mut().for_each(|pulse| *pulse = false);
+            pulses.push(false);
+        } else {
+            pulses.push(true);
+        }
+        last = x;
+    }
+    pulses
+}
+
+/// Finds and classifies every pulse of `pulses` (one level per bit) that takes
+/// part in a bipolar violation. A 0..0V or B0..0V substitution is only taken as
+/// such when it keeps the parity rule, i.e. when the pulses since the last V,
+/// B included, are odd in number.
+pub fn violations(pulses: &[i8], scheme: Scrambling) -> Vec<(usize, Violation)> {
+    let pattern = match scheme {
+        Scrambling::B6ZS => Some(b6zs_pattern as fn(i8) -> Vec<i8>),
+        Scrambling::B8ZS => Some(b8zs_pattern as fn(i8) -> Vec<i8>),
+        _ => None,
+    };
+    let run = match scheme {
+        Scrambling::B3ZS => Some(3),
+        Scrambling::HDB3 => Some(4),
+        _ => None,
+    };
+
+    let mut found = Vec::new();
+    let mut last = -1;
+    let mut marks = 0;
+    let mut i = 0;
+    while i < pulses.len() {
+        let x = pulses[i];
+        if let Some(substitution) = pattern.map(|pattern| pattern(last)) {
+            if pulses[i..].starts_with(&substitution) {
+                let mut previous = last;
+                for (j, &p) in substitution.iter().enumerate().filter(|(_, &p)| p != 0) {
+                    let kind = if p == previous {
+                        Violation::Bipolar
                     } else {
-                        seq = vec![0, 0, 0, -toggle];
-                        non_zero_voltage_bool = !non_zero_voltage_bool;
-                    }
-                    encoded_data.splice(
-                        (encoded_data.len() - 4)..encoded_data.len(),
-                        seq,
-                    );
+                        Violation::Balancing
+                    };
+                    found.push((i + j, kind));
+                    previous = p;
                 }
+                i += substitution.len();
+                continue;
             }
-            '1' => {
-                non_zero_voltage_bool = !non_zero_voltage_bool;
-                toggle *= -1;
-                encoded_data.push(toggle);
-                count = 0;
+        }
+
+        if x != 0 && x == last {
+            let zeros = run.map(|run| run - 1).unwrap_or(0);
+            let preceding = &pulses[i.saturating_sub(zeros)..i];
+            let explained = run.is_some() && preceding.len() == zeros && marks % 2 == 1;
+            if explained && preceding.iter().all(|&p| p == 0) {
+                found.push((i, Violation::Bipolar));
+            } else if explained && preceding[0] == x && preceding[1..].iter().all(|&p| p == 0) {
+                found.push((i - zeros, Violation::Balancing));
+                found.push((i, Violation::Bipolar));
+            } else {
+                found.push((i, Violation::Error));
             }
-            _ => {}
+            marks = 0;
+        } else if x != 0 {
+            marks += 1;
         }
+        if x != 0 {
+            last = x;
+        }
+        i += 1;
     }
+    found
+}
 
-    encoded_data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LfsrMode {
+    /// The register runs free from its seed and its output is added to the data,
+    /// so scrambler and descrambler have to start in step.
+    Additive,
+    /// The register is fed with the scrambled bits, so the descrambler locks on
+    /// after as many bits as the polynomial's degree.
+    SelfSynchronizing,
+}
+
+/// Linear-feedback shift register scrambler for the polynomial with the exponents
+/// in `taps` plus the constant term, e.g. [58, 39] for x^58 + x^39 + 1. The register
+/// holds the most recent bit in bit 0.
+pub struct Lfsr {
+    pub taps: Vec<usize>,
+    pub seed: u64,
+    pub mode: LfsrMode,
+}
+
+impl Lfsr {
+    /// The self-synchronous scrambler of the 64b/66b PCS, x^58 + x^39 + 1.
+    pub fn pcs() -> Self {
+        Lfsr {
+            taps: vec![58, 39],
+            seed: u64::MAX,
+            mode: LfsrMode::SelfSynchronizing,
+        }
+    }
+
+    /// Parses a polynomial such as "x^7 + x^4 + 1" into its taps.
+    pub fn parse_taps(polynomial: &str) -> Option<Vec<usize>> {
+        let mut taps = Vec::new();
+        for term in polynomial.split('+').map(|term| term.trim()) {
+            match term {
+                "1" => {}
+                "x" => taps.push(1),
+                _ => taps.push(term.strip_prefix("x^")?.parse().ok()?),
+            }
+        }
+        if taps.is_empty() || taps.iter().any(|&tap| tap == 0 || tap > 64) {
+            return None;
+        }
+        Some(taps)
+    }
+
+    fn feedback(&self, state: u64) -> u64 {
+        self.taps.iter().fold(0, |acc, &tap| acc ^ (state >> (tap - 1)) & 1)
+    }
+
+    /// Scrambles `data` starting from `state`, which is left where the next block
+    /// has to continue.
+    pub fn scramble(&self, data: &str, state: &mut u64) -> String {
+        data.chars()
+            .map(|c| {
+                let feedback = self.feedback(*state);
+                let bit = (c == '1') as u64 ^ feedback;
+                *state = match self.mode {
+                    LfsrMode::Additive => (*state << 1) | feedback,
+                    LfsrMode::SelfSynchronizing => (*state << 1) | bit,
+                };
+                if bit == 1 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+
+    pub fn descramble(&self, data: &str, state: &mut u64) -> String {
+        data.chars()
+            .map(|c| {
+                let feedback = self.feedback(*state);
+                let bit = (c == '1') as u64;
+                *state = match self.mode {
+                    LfsrMode::Additive => (*state << 1) | feedback,
+                    LfsrMode::SelfSynchronizing => (*state << 1) | bit,
+                };
+                if bit ^ feedback == 1 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hdb3_vectors() {
+        // A leading run counts as after an even number of pulses: B00V, with the
+        // pulse before the line taken as negative.
+        assert_eq!(hdb3("0000", '1'), [1, 0, 0, 1]);
+        assert_eq!(hdb3("00000000", '1'), [1, 0, 0, 1, -1, 0, 0, -1]);
+        // Odd count since the last V: 000V, V repeating the last pulse.
+        assert_eq!(hdb3("10000", '1'), [1, 0, 0, 0, 1]);
+        // Even count: B00V, B alternating and V repeating B.
+        assert_eq!(hdb3("110000", '1'), [1, -1, 1, 0, 0, 1]);
+        assert_eq!(hdb3("100000000", '1'), [1, 0, 0, 0, 1, -1, 0, 0, -1]);
+        assert_eq!(hdb3("1100001000011", '1'), [1, -1, 1, 0, 0, 1, -1, 0, 0, 0, -1, 1, -1]);
+    }
+
+    #[test]
+    fn b8zs_vectors() {
+        assert_eq!(b8zs("10000000011", '1'), [1, 0, 0, 0, 1, -1, 0, -1, 1, -1, 1]);
+        assert_eq!(b8zs("110000000011", '1'), [1, -1, 0, 0, 0, -1, 1, 0, 1, -1, 1, -1]);
+        assert_eq!(b8zs("0000000000000000", '1'), [0, 0, 0, -1, 1, 0, 1, -1, 0, 0, 0, -1, 1, 0, 1, -1]);
+        // Seven zeros are left alone.
+        assert_eq!(b8zs("100000001", '1'), [1, 0, 0, 0, 0, 0, 0, 0, -1]);
+    }
+
+    #[test]
+    fn b3zs_and_b6zs_vectors() {
+        assert_eq!(b3zs("000", '1'), [1, 0, 1]);
+        assert_eq!(b3zs("1000", '1'), [1, 0, 0, 1]);
+        assert_eq!(b3zs("11000", '1'), [1, -1, 1, 0, 1]);
+        assert_eq!(b3zs("000000", '1'), [1, 0, 1, -1, 0, -1]);
+        assert_eq!(b6zs("000000", '1'), [0, -1, 1, 0, 1, -1]);
+        assert_eq!(b6zs("1000000", '1'), [1, 0, 1, -1, 0, -1, 1]);
+        assert_eq!(b6zs("11000000000000", '1'), [1, -1, 0, -1, 1, 0, 1, -1, 0, -1, 1, 0, 1, -1]);
+    }
+
+    #[test]
+    fn pseudoternary_substitutes_runs_of_ones() {
+        assert_eq!(hdb3("1111", '0'), [1, 0, 0, 1]);
+        assert_eq!(b8zs("01111111100", '0'), [1, 0, 0, 0, 1, -1, 0, -1, 1, -1, 1]);
+    }
+
+    #[test]
+    fn parity_substitutions_alternate_their_violations() {
+        let data: String = (0..400).map(|i: u32| if i.wrapping_mul(2654435761).is_multiple_of(7) { '1' } else { '0' }).collect();
+        for (scheme, encoded) in [(Scrambling::B3ZS, b3zs(&data, '1')), (Scrambling::HDB3, hdb3(&data, '1'))] {
+            let found = violations(&encoded, scheme);
+            assert!(found.iter().all(|&(_, kind)| kind != Violation::Error));
+            let polarities: Vec<i8> = found
+                .iter()
+                .filter(|&&(_, kind)| kind == Violation::Bipolar)
+                .map(|&(i, _)| encoded[i])
+                .collect();
+            assert!(polarities.len() > 2);
+            assert!(polarities.windows(2).all(|pair| pair[0] == -pair[1]), "{:?}", scheme);
+        }
+    }
+
+    #[test]
+    fn parity_rule_is_checked() {
+        // The count starts even, so a leading run needs B00V and not 000V.
+        assert_eq!(violations(&[1, 0, 0, 1], Scrambling::HDB3), [(0, Violation::Balancing), (3, Violation::Bipolar)]);
+        assert_eq!(violations(&[0, 0, 0, -1], Scrambling::HDB3), [(3, Violation::Error)]);
+        // One pulse since the last V calls for 000V, two for B00V.
+        assert_eq!(violations(&[1, 0, 0, 0, 1], Scrambling::HDB3), [(4, Violation::Bipolar)]);
+        assert_eq!(violations(&[1, -1, 0, 0, -1], Scrambling::HDB3), [(4, Violation::Error)]);
+        assert_eq!(violations(&[1, -1, 0, 0, 0, -1], Scrambling::HDB3), [(5, Violation::Error)]);
+        assert_eq!(violations(&[1, -1, 1, 0, 0, 1], Scrambling::HDB3), [(2, Violation::Balancing), (5, Violation::Bipolar)]);
+        // The same for B3ZS, with the count starting afresh after every V.
+        let b0v = [(0, Violation::Balancing), (2, Violation::Bipolar)];
+        assert_eq!(violations(&[1, 0, 1, -1, 0, 0, -1], Scrambling::B3ZS), [&b0v[..], &[(6, Violation::Bipolar)]].concat());
+        assert_eq!(violations(&[1, 0, 1, -1, 1, 0, 1], Scrambling::B3ZS), [&b0v[..], &[(6, Violation::Error)]].concat());
+        assert_eq!(violations(&[1, 0, 1, 0, 0, 1], Scrambling::B3ZS), [&b0v[..], &[(5, Violation::Error)]].concat());
+    }
+
+    fn lfsr_data() -> String {
+        (0..300u32).map(|i| if (i * i + 3 * i) % 5 < 2 { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn lfsr_round_trips() {
+        for mode in [LfsrMode::Additive, LfsrMode::SelfSynchronizing] {
+            let lfsr = Lfsr {
+                taps: Lfsr::parse_taps("x^7 + x^4 + 1").unwrap(),
+                seed: 0x5a,
+                mode,
+            };
+            let data = lfsr_data();
+            let (mut scrambler, mut descrambler) = (lfsr.seed, lfsr.seed);
+            let scrambled = lfsr.scramble(&data, &mut scrambler);
+            assert_ne!(scrambled, data);
+            assert_eq!(lfsr.descramble(&scrambled, &mut descrambler), data, "{:?}", mode);
+            assert_eq!(scrambler, descrambler);
+        }
+    }
+
+    #[test]
+    fn lfsr_continues_across_blocks() {
+        let lfsr = Lfsr::pcs();
+        let data = lfsr_data();
+        let mut whole = lfsr.seed;
+        let expected = lfsr.scramble(&data, &mut whole);
+        let mut state = lfsr.seed;
+        let blocks: String = data
+            .as_bytes()
+            .chunks(64)
+            .map(|block| lfsr.scramble(std::str::from_utf8(block).unwrap(), &mut state))
+            .collect();
+        assert_eq!(blocks, expected);
+    }
+
+    #[test]
+    fn self_synchronizing_descrambler_locks_on() {
+        let lfsr = Lfsr::pcs();
+        let data = lfsr_data();
+        let mut state = lfsr.seed;
+        let scrambled = lfsr.scramble(&data, &mut state);
+        // A descrambler that starts in the wrong state recovers after 58 bits.
+        let mut wrong = 0x0123_4567_89ab_cdef;
+        let descrambled = lfsr.descramble(&scrambled, &mut wrong);
+        assert_ne!(descrambled[..58], data[..58]);
+        assert_eq!(descrambled[58..], data[58..]);
+    }
+
+    #[test]
+    fn additive_scrambler_needs_the_seed() {
+        let lfsr = Lfsr {
+            taps: vec![7, 4],
+            seed: 0x7f,
+            mode: LfsrMode::Additive,
+        };
+        let data = lfsr_data();
+        let mut state = lfsr.seed;
+        let scrambled = lfsr.scramble(&data, &mut state);
+        let mut wrong = 0x01;
+        assert_ne!(lfsr.descramble(&scrambled, &mut wrong)[100..], data[100..]);
+    }
+
+    #[test]
+    fn lfsr_taps() {
+        assert_eq!(Lfsr::parse_taps("x^58 + x^39 + 1"), Some(vec![58, 39]));
+        assert_eq!(Lfsr::parse_taps("x^7 + x + 1"), Some(vec![7, 1]));
+        assert_eq!(Lfsr::parse_taps("x^65 + 1"), None);
+        assert_eq!(Lfsr::parse_taps("1"), None);
+        assert_eq!(Lfsr::parse_taps("y^3 + 1"), None);
+    }
 }
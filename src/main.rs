@@ -1,3 +1,5 @@
+#![allow(clippy::upper_case_acronyms)]
+
 mod analog;
 mod digital;
 mod utils;
@@ -8,6 +10,17 @@ use nannou_egui::{self, egui, Egui};
 use utils::{draw_grid, Settings, SignalType};
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--ber") {
+        match crate::digital::ber::report(&args[1..]) {
+            Ok(csv) => print!("{}", csv),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
     nannou::app(model).update(update).run();
 }
 
@@ -63,6 +76,14 @@ fn update(app: &App, model: &mut Model, update: Update) {
                 crate::analog::draw_ui(app, ui, &mut model.signal_type, &mut model.settings);
             }
         });
+
+    crate::digital::update(&mut model.settings.digital);
+    let ber = &mut model.settings.digital.ber;
+    let mut open = ber.show_plot;
+    egui::Window::new("BER vs Eb/N0")
+        .open(&mut open)
+        .show(&ctx, |ui| crate::digital::ber::draw_plot(ui, ber));
+    ber.show_plot = open;
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
@@ -75,9 +96,9 @@ fn view(app: &App, model: &Model, frame: Frame) {
     draw_grid(&draw, &win, 25.0, 0.5);
 
     if model.signal_type == SignalType::Digital {
-        model.settings.digital.encoding.draw_encoding(&model.settings.digital.result, &app, &draw)
+        crate::digital::view(&model.settings.digital, app, &draw)
     } else {
-        model.settings.analog.modulation.draw_modulation(&model, &app, &draw);
+        model.settings.analog.modulation.draw_modulation(model, app, &draw);
     }
 
     draw.to_frame(app, &frame).unwrap();
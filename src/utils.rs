@@ -1,6 +1,13 @@
 use crate::analog::Parameters;
 use crate::analog::{modulation::DM, AnalogSettings, AnalogSignal};
-use crate::digital::{encoding::*, scramble::Scrambling, DigitalSettings};
+use crate::digital::scramble::{Lfsr, LfsrMode, Scrambling};
+use crate::digital::ber::Ber;
+use crate::digital::channel::Channel;
+use crate::digital::crc::{Crc, CrcKind};
+use crate::digital::eye::Eye;
+use crate::digital::fec::ChannelCoding;
+use crate::digital::interleave::Interleaving;
+use crate::digital::{block::BlockCoding, encoding::*, framing::Framing, DigitalSettings};
 use nannou::prelude::{pt2, Draw, Rect, GRAY};
 
 #[derive(PartialEq)]
@@ -20,9 +27,56 @@ impl Settings {
             digital: DigitalSettings {
                 binary_stream: String::new(),
                 longest_palindrome: String::new(),
+                checked_stream: String::new(),
+                checksum: String::new(),
+                framed_stream: String::new(),
+                protected_stream: String::new(),
+                interleaved_stream: String::new(),
+                unscrambled_stream: String::new(),
+                coded_stream: String::new(),
+                blocks: Vec::new(),
+                highlights: Vec::new(),
                 result: Vec::new(),
+                waveform: Vec::new(),
+                received: Vec::new(),
+                decoded_stream: String::new(),
+                decoded_message: String::new(),
+                decode_errors: Vec::new(),
+                corrections: Vec::new(),
+                trellis: Vec::new(),
+                bit_flips: String::new(),
+                flips: Vec::new(),
+                bit_erasures: String::new(),
+                erasures: Vec::new(),
+                channel_errors: Vec::new(),
+                violations: Vec::new(),
                 encoding: Box::new(NRZL),
                 scrambling: Scrambling::None,
+                crc: CrcKind::None,
+                crc_polynomial: "x^3 + x + 1".to_string(),
+                custom_crc: Crc::parse("x^3 + x + 1").unwrap(),
+                crc_append: false,
+                framing: Framing::None,
+                channel_coding: ChannelCoding::None,
+                generators: "7, 5".to_string(),
+                interleaving: Interleaving::None,
+                block_coding: BlockCoding::None,
+                control_symbols: false,
+                lfsr_enabled: false,
+                lfsr_polynomial: String::from("x^7 + x^4 + 1"),
+                lfsr: Lfsr {
+                    taps: vec![7, 4],
+                    seed: 0x7f,
+                    mode: LfsrMode::Additive,
+                },
+                channel: Channel {
+                    enabled: false,
+                    eb_n0: 10.0,
+                    seed: 1,
+                    samples: 16,
+                },
+                ber: Ber::default(),
+                eye: Eye::default(),
             },
             analog: AnalogSettings {
                 analog_signal: AnalogSignal::Sine,